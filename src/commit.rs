@@ -1,14 +1,27 @@
+use crate::config::Member;
 use crate::config::load_config;
+use crate::flow::{Flow, Step};
+use crate::issue::{IssueProvider, provider};
+use crate::team::{
+    history_authors, is_me, me, member, my_emails, remember_roles, remembered_roles,
+};
 use crate::tree::get_tree;
-use crate::utils::types;
-use breathes::hooks::{ok, run_hooks};
+use crate::utils::EmailValidator;
+use crate::utils::{ok, types};
+use breathes::hooks::run_hooks;
+use crossterm::style::Stylize;
 use inquire::error::InquireResult;
+use inquire::validator::Validation;
 use inquire::{Confirm, Editor, InquireError, MultiSelect, Select, Text};
+use regex::Regex;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 use std::io::Error;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::str::FromStr;
+use std::sync::Arc;
 
 #[derive(Deserialize)]
 pub struct Config {
@@ -50,20 +63,15 @@ pub fn add() -> Result<(), Error> {
     if run_hooks().is_ok() {
         let selected = MultiSelect::new("", get_tree())
             .prompt()
-            .expect("failed to select");
+            .map_err(Error::other)?;
         let vcs = vcs();
         for file in &selected {
-            assert!(
-                ok(
-                    "added file",
-                    std::process::Command::new(vcs.as_str())
-                        .arg("add")
-                        .arg(file.as_str()),
-                    "added file",
-                    "failed to add file",
-                )
-                .is_ok()
-            );
+            ok(
+                format!("adding {file}").as_str(),
+                Command::new(vcs.as_str()).arg("add").arg(file.as_str()),
+                format!("added {file}").as_str(),
+                format!("failed to add {file}").as_str(),
+            )?;
         }
         return Ok(());
     }
@@ -92,6 +100,63 @@ pub fn diff() -> Result<(), Error> {
     }
 }
 
+fn files(output: std::io::Result<std::process::Output>) -> Vec<String> {
+    output
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+///
+/// List the files about to be committed
+///
+#[must_use]
+pub fn staged_files() -> Vec<String> {
+    if vcs().eq("hg") {
+        files(Command::new("hg").args(["status", "-amr", "-n"]).output())
+    } else {
+        files(
+            Command::new("git")
+                .args(["diff", "--cached", "--name-only"])
+                .output(),
+        )
+    }
+}
+
+///
+/// List the files changed by a revision
+///
+#[must_use]
+pub fn revision_files(rev: &str) -> Vec<String> {
+    if vcs().eq("hg") {
+        files(
+            Command::new("hg")
+                .args(["log", "-r", rev, "-T", "{join(files, '\\n')}"])
+                .output(),
+        )
+    } else {
+        files(
+            Command::new("git")
+                .args([
+                    "diff-tree",
+                    "--no-commit-id",
+                    "--name-only",
+                    "-r",
+                    "--root",
+                    rev,
+                ])
+                .output(),
+        )
+    }
+}
+
 ///
 /// Display the status of the working directory
 ///
@@ -110,6 +175,9 @@ pub fn status() -> Result<(), Error> {
 ///
 /// Returns an error if the underlying VCS `commit` command fails.
 pub fn run_commit(c: &mut Commit) -> Result<i32, Error> {
+    if !c.rewrite.is_empty() {
+        return reword(c);
+    }
     if Command::new(vcs())
         .arg("commit")
         .arg("-m")
@@ -125,19 +193,385 @@ pub fn run_commit(c: &mut Commit) -> Result<i32, Error> {
     }
 }
 
-#[derive(Default, Debug, Clone)]
+///
+/// Rewrite the message of an existing revision with the commit
+///
+/// The working directory parent is amended, older revisions are reworded with a rebase on Git
+/// and `histedit` on Mercurial.
+///
+/// # Errors
+///
+/// Returns an error if the underlying VCS command fails.
+pub fn reword(c: &Commit) -> Result<i32, Error> {
+    let head = revision_id(if vcs().eq("hg") { "." } else { "HEAD" });
+    let success = if head.as_deref() == Some(c.rewrite.as_str()) {
+        Command::new(vcs())
+            .arg("commit")
+            .arg("--amend")
+            .arg("-m")
+            .arg(c.to_string().as_str())
+            .current_dir(".")
+            .spawn()?
+            .wait()?
+            .success()
+    } else {
+        let path = draft_path().with_file_name("reword.txt");
+        let editor = format!("cp '{}'", path.display());
+        if vcs().eq("hg") {
+            std::fs::write(&path, c.to_string())?;
+            let mut commands = String::new();
+            let Some(descendants) = revisions(format!("{}::.", c.rewrite).as_str()) else {
+                return Err(Error::other("failed to list revisions to rewrite"));
+            };
+            for (i, node) in descendants.iter().enumerate() {
+                let action = if i == 0 { "mess" } else { "pick" };
+                commands.push_str(format!("{action} {node}\n").as_str());
+            }
+            let plan = path.with_file_name("histedit.txt");
+            std::fs::write(&plan, commands)?;
+            Command::new("hg")
+                .arg("histedit")
+                .arg("--commands")
+                .arg(plan.as_os_str())
+                .arg(c.rewrite.as_str())
+                .env("HGEDITOR", editor.as_str())
+                .spawn()?
+                .wait()?
+                .success()
+        } else {
+            let old = subject(c.rewrite.as_str()).unwrap_or_default();
+            std::fs::write(&path, format!("amend! {old}\n\n{c}"))?;
+            let parent = revision_id(format!("{}~1", c.rewrite).as_str());
+            Command::new("git")
+                .arg("commit")
+                .arg("--quiet")
+                .arg(format!("--fixup=reword:{}", c.rewrite))
+                .env("GIT_EDITOR", editor.as_str())
+                .spawn()?
+                .wait()?
+                .success()
+                && Command::new("git")
+                    .args([
+                        "rebase",
+                        "--quiet",
+                        "--interactive",
+                        "--autosquash",
+                        "--autostash",
+                    ])
+                    .arg(parent.unwrap_or_else(|| String::from("--root")))
+                    .env("GIT_SEQUENCE_EDITOR", "true")
+                    .spawn()?
+                    .wait()?
+                    .success()
+        }
+    };
+    if success {
+        Ok(0)
+    } else {
+        Err(Error::other("failed to rewrite commit"))
+    }
+}
+
+///
+/// Resolve a revision to its full identifier
+///
+#[must_use]
+pub fn revision_id(rev: &str) -> Option<String> {
+    revisions(rev)?.into_iter().next()
+}
+
+fn revisions(revset: &str) -> Option<Vec<String>> {
+    let output = if vcs().eq("hg") {
+        Command::new("hg")
+            .args(["log", "-r", revset, "-T", "{node}\n"])
+            .output()
+    } else {
+        Command::new("git")
+            .args(["rev-parse", "--verify", "--quiet", revset])
+            .output()
+    }
+    .ok()?;
+    let ids = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(String::from)
+        .collect::<Vec<String>>();
+    (output.status.success() && !ids.is_empty()).then_some(ids)
+}
+
+///
+/// Get the full message of a revision
+///
+#[must_use]
+pub fn message(rev: &str) -> Option<String> {
+    let output = if vcs().eq("hg") {
+        Command::new("hg")
+            .args(["log", "-r", rev, "-T", "{desc}"])
+            .output()
+    } else {
+        Command::new("git")
+            .args(["log", "-1", "--format=%B", rev])
+            .output()
+    }
+    .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+///
+/// Get the name of the current branch
+///
+#[must_use]
+pub fn branch() -> Option<String> {
+    let output = if vcs().eq("hg") {
+        Command::new("hg").arg("branch").output()
+    } else {
+        Command::new("git")
+            .args(["rev-parse", "--abbrev-ref", "HEAD"])
+            .output()
+    }
+    .ok()?;
+    let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !branch.is_empty() && branch.ne("HEAD")).then_some(branch)
+}
+
+///
+/// The revisions of the current branch which are not on the default branch
+///
+#[must_use]
+pub fn branch_revset() -> String {
+    if vcs().eq("hg") {
+        String::from("only(., default)")
+    } else {
+        let base = ["origin/HEAD", "main", "master"]
+            .into_iter()
+            .find(|base| revision_id(base).is_some())
+            .unwrap_or("HEAD");
+        format!("{base}..HEAD")
+    }
+}
+
+///
+/// A revision of the history, with its author, its date and its message
+///
+#[derive(Debug, Clone)]
+pub struct Revision {
+    pub id: String,
+    pub author: String,
+    pub date: String,
+    pub message: String,
+}
+
+///
+/// Get the revisions of a revset, newest first, keeping only those after `since` when given
+///
+/// Merges are left out, their messages are written by the VCS.
+///
+#[must_use]
+pub fn history(revset: &str, since: Option<&str>) -> Vec<Revision> {
+    let output = if vcs().eq("hg") {
+        let revset = since.map_or_else(
+            || format!("({revset}) and not merge()"),
+            |since| format!("({revset}) and not merge() and date(\">{since}\")"),
+        );
+        Command::new("hg")
+            .args([
+                "log",
+                "-r",
+                revset.as_str(),
+                "-T",
+                "{node}\n{author|person}\n{date|shortdate}\n{desc}\0",
+            ])
+            .output()
+    } else {
+        let mut cmd = Command::new("git");
+        cmd.args(["log", "--no-merges", "--format=%H%n%an%n%as%n%B%x00"]);
+        if let Some(since) = since {
+            cmd.arg(format!("--since={since}"));
+        }
+        cmd.arg(revset).arg("--").output()
+    };
+    output
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .split('\0')
+                .filter_map(|entry| {
+                    let mut lines = entry.trim_start().splitn(4, '\n');
+                    Some(Revision {
+                        id: lines.next().filter(|id| !id.is_empty())?.to_string(),
+                        author: lines.next()?.to_string(),
+                        date: lines.next()?.to_string(),
+                        message: lines.next()?.to_string(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+///
+/// Get the id and the message of every revision of a revset
+///
+#[must_use]
+pub fn messages(revset: &str) -> Vec<(String, String)> {
+    history(revset, None)
+        .into_iter()
+        .map(|revision| (revision.id, revision.message))
+        .collect()
+}
+
+///
+/// Get the issue keys of a branch name, the first matching group of each match
+///
+fn branch_keys(pattern: &str, name: &str) -> Vec<String> {
+    let Ok(re) = Regex::new(pattern) else {
+        return Vec::new();
+    };
+    re.captures_iter(name)
+        .filter_map(|caps| caps.iter().skip(1).flatten().next().or_else(|| caps.get(0)))
+        .map(|id| id.as_str().to_string())
+        .collect()
+}
+
+///
+/// Get the issues referenced by the branch name and the commits of the branch
+///
+#[must_use]
+pub fn branch_issues(pattern: &str) -> Vec<String> {
+    let mut issues = branch()
+        .map(|name| branch_keys(pattern, name.as_str()))
+        .unwrap_or_default();
+    for (_, message) in messages(branch_revset().as_str()) {
+        if let Ok(commit) = Commit::from_str(message.trim()) {
+            issues.extend(commit.resolves);
+        }
+    }
+    let mut seen = Vec::new();
+    issues.retain(|issue| {
+        let new = !seen.contains(issue);
+        seen.push(issue.clone());
+        new
+    });
+    issues
+}
+
+///
+/// Get the first line of the message of a revision
+///
+#[must_use]
+pub fn subject(rev: &str) -> Option<String> {
+    let output = if vcs().eq("hg") {
+        Command::new("hg")
+            .args(["log", "-r", rev, "-T", "{desc|firstline}"])
+            .output()
+    } else {
+        Command::new("git")
+            .args(["log", "-1", "--format=%s", rev])
+            .output()
+    }
+    .ok()?;
+    let subject = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !subject.is_empty()).then_some(subject)
+}
+
+///
+/// The file keeping the in-progress wizard of a new commit
+///
+#[must_use]
+pub fn draft_path() -> PathBuf {
+    Path::new(format!(".{}", vcs()).as_str())
+        .join("breath")
+        .join("draft.yml")
+}
+
+///
+/// The file keeping the in-progress wizard of an amend or a reword
+///
+#[must_use]
+pub fn rewrite_draft_path() -> PathBuf {
+    draft_path().with_file_name("rewrite.yml")
+}
+
+///
+/// Drop a saved draft
+///
+/// # Errors
+///
+/// Returns an error if the draft exists but cannot be removed.
+pub fn discard_draft(path: &Path) -> Result<(), Error> {
+    if path.is_file() {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+///
+/// Ask whether to resume or discard the draft left by an interrupted wizard
+///
+/// # Errors
+///
+/// On bad user inputs, or if the draft cannot be removed
+pub fn resume_draft(path: &Path, title: &str) -> InquireResult<bool> {
+    let resume = Select::new(title, vec!["Resume the draft", "Discard the draft"])
+        .prompt()?
+        .starts_with("Resume");
+    if !resume {
+        discard_draft(path)?;
+    }
+    Ok(resume)
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Commit {
     pub t: String,
+    pub revision: String,
+    pub version: String,
     pub scopes: Vec<String>,
     pub summary: String,
     pub why: String,
     pub who: String,
     pub roles: Vec<String>,
+    pub co_authors: Vec<Member>,
     pub what: String,
     pub benefits: String,
     pub breaking_changes: String,
     pub notes: String,
     pub resolves: Vec<String>,
+    pub keyword: String,
+    pub rewrite: String,
+    pub done: Vec<Step>,
+    pub style: Style,
+    #[serde(skip)]
+    pub flow: Flow,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[doc = "The header style of the commit message"]
+pub enum Style {
+    #[default]
+    Breath,
+    Conventional,
+}
+
+impl Style {
+    #[must_use]
+    pub fn all() -> Vec<Self> {
+        vec![Self::Breath, Self::Conventional]
+    }
+}
+
+impl Display for Style {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Breath => write!(f, "breath ~ type(scopes) ~ summary"),
+            Self::Conventional => write!(f, "conventional ~ type(scopes)!: summary"),
+        }
+    }
 }
 
 #[derive(Ord, PartialOrd, Eq, PartialEq, Debug, Clone)]
@@ -189,97 +623,472 @@ impl Display for Role {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[doc = "An action of the commit review screen"]
+pub enum Review {
+    Commit,
+    Type,
+    Step(Step),
+    Message,
+    Abort,
+}
+
+impl Display for Review {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Commit => write!(f, "Commit"),
+            Self::Type => write!(f, "Edit type"),
+            Self::Step(step) => write!(f, "Edit {}", step.to_string().replace('_', " ")),
+            Self::Message => write!(f, "Edit the full message in $EDITOR"),
+            Self::Abort => write!(f, "Abort"),
+        }
+    }
+}
+
+fn selected(options: &[String], values: &[String]) -> Vec<usize> {
+    options
+        .iter()
+        .enumerate()
+        .filter(|(_, option)| values.contains(option))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+fn section(f: &mut Formatter<'_>, title: &str, text: &str) -> std::fmt::Result {
+    if text.trim().is_empty() {
+        return Ok(());
+    }
+    writeln!(f, "\n\t{title}\n")?;
+    for line in text.split('\n') {
+        if line.is_empty() {
+            writeln!(f, "\n")?;
+            continue;
+        }
+        writeln!(f, "\t\t* {line}")?;
+    }
+    Ok(())
+}
+
 impl Display for Commit {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        writeln!(
-            f,
-            "{}({}) ~ {}",
-            self.t,
-            self.scopes.join(","),
-            self.summary
-        )?;
-        writeln!(f, "\n\tWhy changes?\n")?;
-        let why_lines = self.why.split('\n').collect::<Vec<&str>>();
-        for line in why_lines {
-            if line.is_empty() {
-                writeln!(f, "\n")?;
-                continue;
-            }
-            writeln!(f, "\t\t* {line}")?;
+        match self.style {
+            Style::Breath => writeln!(
+                f,
+                "{}({}) ~ {}",
+                self.t,
+                self.scopes.join(","),
+                self.summary
+            )?,
+            Style::Conventional => writeln!(
+                f,
+                "{}{}{}: {}",
+                self.t,
+                if self.scopes.is_empty() {
+                    String::new()
+                } else {
+                    format!("({})", self.scopes.join(","))
+                },
+                if self.breaking_changes.is_empty() {
+                    ""
+                } else {
+                    "!"
+                },
+                self.summary
+            )?,
         }
-        writeln!(f, "\n\tBreaking Changes:\n")?;
-        let breaking_changes_lines = self.breaking_changes.split('\n').collect::<Vec<&str>>();
-        for line in breaking_changes_lines {
-            if line.is_empty() {
-                writeln!(f, "\n")?;
-                continue;
+        section(f, "Reverts:", self.revision.as_str())?;
+        section(f, "Version:", self.version.as_str())?;
+        section(f, "Why changes?", self.why.as_str())?;
+        section(f, "Breaking Changes:", self.breaking_changes.as_str())?;
+        section(f, "What changes?", self.what.as_str())?;
+        if !self.who.is_empty() {
+            writeln!(f, "\n\tWho changes?\n")?;
+            writeln!(
+                f,
+                "\t\t* @{} ~ {} ",
+                self.who,
+                self.roles.join(" ").as_str()
+            )?;
+            for author in &self.co_authors {
+                writeln!(
+                    f,
+                    "\t\t* @{} ~ {} ",
+                    author.handle,
+                    author.roles.join(" ").as_str()
+                )?;
             }
-            writeln!(f, "\t\t* {line}")?;
         }
-        writeln!(f, "\n\tWhat changes?\n")?;
-        let what_lines = self.what.split('\n').collect::<Vec<&str>>();
-        for line in what_lines {
-            if line.is_empty() {
-                writeln!(f, "\n")?;
-                continue;
-            }
-            writeln!(f, "\t\t* {line}")?;
-        }
-        writeln!(f, "\n\tWho changes?\n")?;
-        writeln!(
-            f,
-            "\t\t* @{} ~ {} ",
-            self.who,
-            self.roles.join(" ").as_str()
-        )?;
-        writeln!(f, "\n\tBenefits:\n")?;
-        let benefits_lines = self.benefits.split('\n').collect::<Vec<&str>>();
-        for line in benefits_lines {
-            if line.is_empty() {
-                writeln!(f, "\n")?;
-                continue;
+        section(f, "Benefits:", self.benefits.as_str())?;
+        section(f, "Notes:", self.notes.as_str())?;
+        if !self.resolves.is_empty() {
+            writeln!(f, "\n\tResolves\n")?;
+            for resolve in &self.resolves {
+                let issue = resolve.split('~').collect::<Vec<&str>>();
+                let re = issue.first().expect("bad resolve").trim();
+                let keyword = if !self.keyword.is_empty() {
+                    self.keyword.as_str()
+                } else if re.chars().all(|c| c.is_ascii_digit()) {
+                    "Fixes #{id}"
+                } else {
+                    "Fixes {id}"
+                };
+                writeln!(f, "\t\t{}", keyword.replace("{id}", re))?;
             }
-            writeln!(f, "\t\t* {line}")?;
         }
-        writeln!(f, "\n\tNotes:\n")?;
-        let notes_lines = self.notes.split('\n').collect::<Vec<&str>>();
-        for line in notes_lines {
-            if line.is_empty() {
-                writeln!(f, "\n")?;
-                continue;
+        let trailers = self
+            .co_authors
+            .iter()
+            .filter(|author| !author.email.is_empty())
+            .collect::<Vec<&Member>>();
+        if !trailers.is_empty() {
+            writeln!(f)?;
+            for author in trailers {
+                let name = if author.name.is_empty() {
+                    author.handle.as_str()
+                } else {
+                    author.name.as_str()
+                };
+                writeln!(f, "Co-authored-by: {name} <{}>", author.email)?;
             }
-            writeln!(f, "\t\t* {line}")?;
-        }
-        writeln!(f, "\n\tResolves\n")?;
-        for resolve in &self.resolves {
-            let issue = resolve.split('~').collect::<Vec<&str>>();
-            let re = issue.first().expect("bad resolve");
-            writeln!(f, "\t\tFixes #{}", re.trim())?;
         }
         writeln!(f, "\n")?;
         Ok(())
     }
 }
+impl FromStr for Commit {
+    type Err = Error;
+
+    fn from_str(message: &str) -> Result<Self, Self::Err> {
+        Self::parse(message).map(|(commit, _)| commit)
+    }
+}
+
 impl Commit {
+    ///
+    /// Parse a message, returning with the commit the lines it has no place for
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header is not a breath or conventional one.
+    pub fn parse(message: &str) -> Result<(Self, Vec<String>), Error> {
+        let mut lines = message.lines();
+        let header = lines.next().unwrap_or_default();
+        let re =
+            Regex::new(r"^([^(:!~ ]+)(?:\(([^)]*)\))?!? ?(~|:) (.*)$").map_err(Error::other)?;
+        let Some(caps) = re.captures(header.trim()) else {
+            return Err(Error::other("not a breath commit message"));
+        };
+        let mut commit = Self {
+            t: caps[1].trim().to_string(),
+            scopes: caps
+                .get(2)
+                .map_or("", |scopes| scopes.as_str())
+                .split(',')
+                .map(str::trim)
+                .filter(|scope| !scope.is_empty())
+                .map(String::from)
+                .collect(),
+            summary: caps[4].trim().to_string(),
+            style: if caps[3].eq(":") {
+                Style::Conventional
+            } else {
+                Style::Breath
+            },
+            ..Self::default()
+        };
+        let issue = Regex::new(r"[A-Z][A-Z0-9]+-\d+|\d+").map_err(Error::other)?;
+        let mut title = String::new();
+        let mut blank = false;
+        let mut co_authors = 0;
+        let mut strays = Vec::new();
+        for line in lines {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                blank = true;
+                continue;
+            }
+            if let Some(item) = trimmed.strip_prefix("* ") {
+                if title == "Who changes?" {
+                    let (who, roles) = item.split_once('~').unwrap_or((item, ""));
+                    let who = who.trim().trim_start_matches('@').to_string();
+                    let roles = roles.split_whitespace().map(String::from).collect();
+                    if commit.who.is_empty() {
+                        commit.who = who;
+                        commit.roles = roles;
+                    } else {
+                        commit.co_authors.push(Member {
+                            handle: who,
+                            name: String::new(),
+                            email: String::new(),
+                            roles,
+                        });
+                    }
+                } else if let Some(text) = commit.section_mut(title.as_str()) {
+                    if !text.is_empty() {
+                        text.push('\n');
+                        if blank {
+                            text.push('\n');
+                        }
+                    }
+                    text.push_str(item);
+                } else {
+                    strays.push(trimmed.to_string());
+                }
+            } else if let Some(trailer) = trimmed.strip_prefix("Co-authored-by:") {
+                let (name, email) = trailer.split_once('<').unwrap_or((trailer, ""));
+                let email = email.trim_end_matches('>').trim();
+                let handle = email.split('@').next().unwrap_or_default();
+                if let Some(author) = commit.co_authors.get_mut(co_authors) {
+                    author.name = name.trim().to_string();
+                    author.email = email.to_string();
+                } else {
+                    commit.co_authors.push(Member {
+                        handle: handle.to_string(),
+                        name: name.trim().to_string(),
+                        email: email.to_string(),
+                        roles: Vec::new(),
+                    });
+                }
+                co_authors += 1;
+            } else if title == "Resolves"
+                && let Some(id) = issue.find(trimmed)
+            {
+                commit.keyword = trimmed.replacen(id.as_str(), "{id}", 1);
+                commit.resolves.push(id.as_str().to_string());
+            } else {
+                if !matches!(trimmed, "Who changes?" | "Resolves")
+                    && commit.section_mut(trimmed).is_none()
+                {
+                    strays.push(trimmed.to_string());
+                }
+                title = trimmed.to_string();
+            }
+            blank = false;
+        }
+        Ok((commit, strays))
+    }
+
     #[must_use]
     pub fn new() -> Self {
         Self::default()
     }
+
+    ///
+    /// Parse the message of an existing revision to rewrite it
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the revision is unknown or its message is not a breath one.
+    pub fn from_revision(rev: &str) -> Result<Self, Error> {
+        let Some(id) = revision_id(rev) else {
+            return Err(Error::other(format!("unknown revision {rev}")));
+        };
+        let Some(message) = message(id.as_str()) else {
+            return Err(Error::other(format!("failed to read the message of {rev}")));
+        };
+        let mut commit = Self::from_str(message.as_str())?;
+        commit.rewrite = id;
+        Ok(commit)
+    }
+
+    fn me(&self, config: &crate::config::Config) -> String {
+        if self.who.is_empty() {
+            me(config)
+        } else {
+            self.who.clone()
+        }
+    }
+
+    fn section_mut(&mut self, title: &str) -> Option<&mut String> {
+        match title {
+            "Reverts:" => Some(&mut self.revision),
+            "Version:" => Some(&mut self.version),
+            "Why changes?" => Some(&mut self.why),
+            "Breaking Changes:" => Some(&mut self.breaking_changes),
+            "What changes?" => Some(&mut self.what),
+            "Benefits:" => Some(&mut self.benefits),
+            "Notes:" => Some(&mut self.notes),
+            _ => None,
+        }
+    }
+
     ///
+    /// Walk through every step again with the current values as defaults
+    ///
+    /// # Panics
+    /// if bad config
+    ///
+    /// # Errors
+    ///
+    /// On bad user inputs
+    ///
+    pub fn edit(&mut self) -> InquireResult<&mut Self> {
+        self.ask_type()?.ask_flow()?.confirm()
+    }
+
+    ///
+    /// Load the draft saved at `path`
+    ///
+    #[must_use]
+    pub fn load_draft(path: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(path).ok()?;
+        serde_yaml::from_str(&content).ok()
+    }
+
+    ///
+    /// Start rewriting `rev`, or resume the interrupted rewrite of it
+    ///
+    /// Returns the commit and whether it comes from a draft.
+    ///
+    /// # Errors
+    ///
+    /// On bad user inputs, on an unknown revision, or if a draft rewrites another revision
+    pub fn rewriting(rev: &str) -> InquireResult<(Self, bool)> {
+        let commit = Self::from_revision(rev)?;
+        let path = rewrite_draft_path();
+        let Some(draft) = Self::load_draft(path.as_path()) else {
+            return Ok((commit, false));
+        };
+        if draft.rewrite.ne(&commit.rewrite) {
+            let short: String = draft.rewrite.chars().take(12).collect();
+            return Err(InquireError::from(Error::other(format!(
+                "a draft rewriting {short} exists, run `breath reword {short}` to finish it or `breath commit --discard` to drop it"
+            ))));
+        }
+        if resume_draft(path.as_path(), "A draft rewriting this commit exists")? {
+            Ok((draft, true))
+        } else {
+            Ok((commit, false))
+        }
+    }
+
+    ///
+    /// The draft file of this commit, new commits and rewrites keep apart
+    ///
+    #[must_use]
+    pub fn draft_path(&self) -> PathBuf {
+        if self.rewrite.is_empty() {
+            draft_path()
+        } else {
+            rewrite_draft_path()
+        }
+    }
+
+    ///
+    /// Save the in-progress commit to its draft file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the draft cannot be written.
+    pub fn save_draft(&self) -> Result<(), Error> {
+        let path = self.draft_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let draft = serde_yaml::to_string(self).map_err(Error::other)?;
+        std::fs::write(path, draft)
+    }
+
+    ///
+    /// Continue the wizard from the last answered step
+    ///
+    /// # Panics
+    /// if bad config
+    ///
+    /// # Errors
+    ///
+    /// On bad user inputs
+    ///
+    pub fn resume(&mut self) -> InquireResult<&mut Self> {
+        if self.t.is_empty() {
+            self.ask_type()?;
+        }
+        self.ask_flow()?.confirm()
+    }
+    fn retype(&mut self) -> InquireResult<()> {
+        self.ask_type()?;
+        self.flow = load_config().breathes.flow(self.t.as_str());
+        self.done = self
+            .flow
+            .steps()
+            .into_iter()
+            .filter(|step| self.is_answered(*step) || !self.flow.is_required(*step))
+            .collect();
+        self.ask_flow()?;
+        Ok(())
+    }
+
+    ///
+    /// Review the message and edit any field before committing
+    ///
+    /// # Panics
+    /// if bad config
     ///
     /// # Errors
     ///
     /// Bad user or cancel by user
     ///
     pub fn confirm(&mut self) -> InquireResult<&mut Self> {
-        println!("{self}");
-        if Confirm::new("Confirm commit?")
-            .with_default(true)
-            .prompt()?
-        {
-            Ok(self)
-        } else {
-            Err(InquireError::from(Error::other("commit aborted")))
+        loop {
+            println!("{self}");
+            let mut actions = vec![Review::Commit, Review::Type];
+            for step in self.flow.steps() {
+                actions.push(Review::Step(step));
+            }
+            actions.push(Review::Message);
+            actions.push(Review::Abort);
+            match Select::new("Confirm commit?", actions).prompt()? {
+                Review::Commit => return Ok(self),
+                Review::Abort => {
+                    return Err(InquireError::from(Error::other("commit aborted")));
+                }
+                Review::Type => {
+                    let before = self.clone();
+                    match self.retype() {
+                        Ok(()) => {}
+                        Err(InquireError::OperationCanceled) => *self = before,
+                        Err(e) => return Err(e),
+                    }
+                }
+                Review::Step(step) => {
+                    let before = self.clone();
+                    match self.ask(step) {
+                        Ok(_) => {}
+                        Err(InquireError::OperationCanceled) => *self = before,
+                        Err(e) => return Err(e),
+                    }
+                }
+                Review::Message => {
+                    let mut message = self.to_string();
+                    loop {
+                        message = match Editor::new("Edit the commit message")
+                            .with_predefined_text(message.as_str())
+                            .prompt()
+                        {
+                            Ok(message) => message,
+                            Err(InquireError::OperationCanceled) => break,
+                            Err(e) => return Err(e),
+                        };
+                        match Self::parse(message.as_str()) {
+                            Ok((edited, strays)) if strays.is_empty() => {
+                                *self = Self {
+                                    rewrite: std::mem::take(&mut self.rewrite),
+                                    done: std::mem::take(&mut self.done),
+                                    flow: std::mem::take(&mut self.flow),
+                                    ..edited
+                                };
+                                break;
+                            }
+                            Ok((_, strays)) => eprintln!(
+                                "these lines belong to no section, make them bullets of one:\n{}",
+                                strays.join("\n")
+                            ),
+                            Err(e) => eprintln!("{e}"),
+                        }
+                    }
+                }
+            }
+            self.save_draft()?;
         }
     }
 
@@ -295,19 +1104,144 @@ impl Commit {
             .show_diff()?
             .add()?
             .ask_type()?
-            .ask_scopes()?
-            .ask_summary()?
-            .ask_roles()?
-            .ask_why()?
-            .breaking_changes()?
-            .ask_what()?
-            .ask_who()?
-            .ask_benefits()?
-            .ask_notes()?
-            .ask_resolves()?
+            .ask_flow()?
             .confirm()
     }
 
+    ///
+    /// Ask every step of the flow configured for the commit type
+    ///
+    /// # Panics
+    /// if bad config
+    ///
+    /// # Errors
+    ///
+    /// On bad user inputs
+    ///
+    pub fn ask_flow(&mut self) -> InquireResult<&mut Self> {
+        let config = load_config().breathes;
+        self.flow = config.flow(self.t.as_str());
+        self.style = config.style;
+        for step in self.flow.steps() {
+            if self.done.contains(&step) {
+                continue;
+            }
+            if let Some(condition) = self.flow.condition(step)
+                && !self.is_answered(condition)
+            {
+                continue;
+            }
+            self.ask(step)?;
+            self.done.push(step);
+            self.save_draft()?;
+        }
+        Ok(self)
+    }
+
+    ///
+    /// Check if a step has been answered
+    ///
+    #[must_use]
+    pub fn is_answered(&self, step: Step) -> bool {
+        match step {
+            Step::Revision => !self.revision.trim().is_empty(),
+            Step::Version => !self.version.trim().is_empty(),
+            Step::Scopes => !self.scopes.is_empty(),
+            Step::Summary => !self.summary.trim().is_empty(),
+            Step::Roles => !self.roles.is_empty(),
+            Step::Why => !self.why.trim().is_empty(),
+            Step::BreakingChanges => !self.breaking_changes.trim().is_empty(),
+            Step::What => !self.what.trim().is_empty(),
+            Step::Who => !self.who.trim().is_empty(),
+            Step::CoAuthors => !self.co_authors.is_empty(),
+            Step::Benefits => !self.benefits.trim().is_empty(),
+            Step::Notes => !self.notes.trim().is_empty(),
+            Step::Resolves => !self.resolves.is_empty(),
+        }
+    }
+
+    ///
+    /// Ask a single step
+    ///
+    /// # Panics
+    /// if bad config
+    ///
+    /// # Errors
+    ///
+    /// On bad user inputs
+    ///
+    pub fn ask(&mut self, step: Step) -> InquireResult<&mut Self> {
+        match step {
+            Step::Revision => self.ask_revision(),
+            Step::Version => self.ask_version(),
+            Step::Scopes => self.ask_scopes(),
+            Step::Summary => self.ask_summary(),
+            Step::Roles => self.ask_roles(),
+            Step::Why => self.ask_why(),
+            Step::BreakingChanges => self.breaking_changes(),
+            Step::What => self.ask_what(),
+            Step::Who => self.ask_who(),
+            Step::CoAuthors => self.ask_co_authors(),
+            Step::Benefits => self.ask_benefits(),
+            Step::Notes => self.ask_notes(),
+            Step::Resolves => self.ask_resolves(),
+        }
+    }
+
+    ///
+    /// Ask the reverted revision and use its subject as summary
+    ///
+    /// # Errors
+    ///
+    /// On bad user inputs
+    ///
+    pub fn ask_revision(&mut self) -> InquireResult<&mut Self> {
+        let current = std::mem::take(&mut self.revision);
+        loop {
+            self.revision.push_str(
+                Text::new("Revision to revert:")
+                    .with_initial_value(current.as_str())
+                    .prompt()?
+                    .trim(),
+            );
+            if !self.revision.is_empty() || !self.flow.is_required(Step::Revision) {
+                break;
+            }
+        }
+        if self.summary.is_empty()
+            && let Some(subject) = subject(self.revision.as_str())
+        {
+            self.summary = format!("revert \"{subject}\"");
+        }
+        Ok(self)
+    }
+
+    ///
+    /// Ask the released version and use it as summary
+    ///
+    /// # Errors
+    ///
+    /// On bad user inputs
+    ///
+    pub fn ask_version(&mut self) -> InquireResult<&mut Self> {
+        let current = std::mem::take(&mut self.version);
+        loop {
+            self.version.push_str(
+                Text::new("Version:")
+                    .with_initial_value(current.as_str())
+                    .prompt()?
+                    .trim(),
+            );
+            if !self.version.is_empty() || !self.flow.is_required(Step::Version) {
+                break;
+            }
+        }
+        if self.summary.is_empty() && !self.version.is_empty() {
+            self.summary = format!("{} {}", self.t, self.version);
+        }
+        Ok(self)
+    }
+
     ///
     /// Show diff between the working directory and the last commit
     ///
@@ -358,9 +1292,18 @@ impl Commit {
     /// On bad user inputs
     ///
     pub fn ask_notes(&mut self) -> InquireResult<&mut Self> {
-        self.notes.clear();
-        self.notes
-            .push_str(Editor::new("The teams notes:").prompt()?.as_str());
+        let current = std::mem::take(&mut self.notes);
+        loop {
+            self.notes.push_str(
+                Editor::new("The teams notes:")
+                    .with_predefined_text(current.as_str())
+                    .prompt()?
+                    .as_str(),
+            );
+            if !self.notes.is_empty() || !self.flow.is_required(Step::Notes) {
+                break;
+            }
+        }
         Ok(self)
     }
 
@@ -372,11 +1315,20 @@ impl Commit {
     /// On bad user inputs
     ///
     pub fn ask_type(&mut self) -> InquireResult<&mut Self> {
+        let types = types();
+        let cursor = types
+            .iter()
+            .position(|t| t.split('~').next().unwrap_or_default().trim() == self.t)
+            .unwrap_or_default();
         self.t.clear();
-        let x = Select::new("Commit types", types()).prompt()?;
+        let x = Select::new("Commit types", types)
+            .with_starting_cursor(cursor)
+            .prompt()?;
         let all = x.split('~').collect::<Vec<&str>>();
         if let Some(t) = all.first() {
-            self.t.push_str(t);
+            self.t.push_str(t.trim());
+            self.done.clear();
+            self.save_draft()?;
             return Ok(self);
         }
         Err(InquireError::from(Error::other("bad commit type")))
@@ -390,23 +1342,59 @@ impl Commit {
     /// On bad user inputs
     ///
     pub fn ask_roles(&mut self) -> InquireResult<&mut Self> {
-        self.roles.clear();
+        let config = load_config().breathes;
+        let roles = config.roles();
         let mut x = Vec::new();
-        let mut r = Role::all();
-        r.sort();
-        for role in &r {
+        for role in &roles {
             x.push(role.to_string());
         }
+        let mut current = roles
+            .iter()
+            .enumerate()
+            .filter(|(_, role)| self.roles.contains(&role.name))
+            .map(|(i, _)| i)
+            .collect::<Vec<usize>>();
+        let me = self.me(&config);
+        if self.roles.is_empty() {
+            let mut defaults = remembered_roles(me.as_str());
+            if defaults.is_empty()
+                && let Some(member) = member(&config, me.as_str())
+            {
+                defaults.clone_from(&member.roles);
+            }
+            current = roles
+                .iter()
+                .enumerate()
+                .filter(|(_, role)| {
+                    defaults.contains(&role.name)
+                        || (defaults.is_empty()
+                            && role.users.iter().any(|user| user == "*" || *user == me))
+                })
+                .map(|(i, _)| i)
+                .collect();
+        }
+        self.roles.clear();
+        let required = self.flow.is_required(Step::Roles);
         while self.roles.is_empty() {
             self.roles.clear();
             if x.is_empty() {
                 return Err(InquireError::from(Error::other("bad roles")));
             }
-            self.roles = MultiSelect::new("Select roles", x.clone()).prompt()?;
+            self.roles = MultiSelect::new("Select roles", x.clone())
+                .with_default(&current)
+                .prompt()?
+                .iter()
+                .filter_map(|role| role.split('~').next())
+                .map(|role| role.trim().to_string())
+                .collect();
+            if !required {
+                break;
+            }
         }
-        if self.roles.is_empty() {
+        if self.roles.is_empty() && required {
             return Err(InquireError::from(Error::other("bad roles")));
         }
+        remember_roles(me.as_str(), &self.roles)?;
         Ok(self)
     }
     ///
@@ -417,13 +1405,20 @@ impl Commit {
     /// On bad user inputs
     ///
     pub fn ask_summary(&mut self) -> InquireResult<&mut Self> {
-        self.summary.clear();
+        let current = std::mem::take(&mut self.summary);
         while self.summary.is_empty() {
             self.summary.clear();
-            self.summary
-                .push_str(Text::new("Commit summary:").prompt()?.as_str());
+            self.summary.push_str(
+                Text::new("Commit summary:")
+                    .with_initial_value(current.as_str())
+                    .prompt()?
+                    .as_str(),
+            );
+            if !self.flow.is_required(Step::Summary) {
+                break;
+            }
         }
-        if self.summary.is_empty() {
+        if self.summary.is_empty() && self.flow.is_required(Step::Summary) {
             return Err(InquireError::from(Error::other("bad summary")));
         }
         Ok(self)
@@ -436,16 +1431,20 @@ impl Commit {
     /// On bad user inputs
     ///
     pub fn ask_why(&mut self) -> InquireResult<&mut Self> {
-        self.why.clear();
+        let current = std::mem::take(&mut self.why);
         while self.why.is_empty() {
             self.why.clear();
             self.why.push_str(
                 Editor::new("Why are you making this change?")
+                    .with_predefined_text(current.as_str())
                     .prompt()?
                     .as_str(),
             );
+            if !self.flow.is_required(Step::Why) {
+                break;
+            }
         }
-        if self.why.is_empty() {
+        if self.why.is_empty() && self.flow.is_required(Step::Why) {
             return Err(InquireError::from(Error::other("bad why")));
         }
         Ok(self)
@@ -460,27 +1459,144 @@ impl Commit {
     /// if bad config
     ///
     pub fn ask_who(&mut self) -> InquireResult<&mut Self> {
+        let config = load_config().breathes;
+        let current = self.me(&config);
         self.who.clear();
+        let handles = config
+            .team
+            .iter()
+            .map(|member| member.handle.clone())
+            .collect::<Vec<String>>();
         while self.who.is_empty() {
             self.who.clear();
-            if cfg!(windows) {
-                self.who
-                    .push_str(Text::new("Who are you:").prompt()?.as_str());
-            } else {
-                self.who.push_str(
-                    Text::new("Who are you:")
-                        .with_default(env!("USER"))
-                        .prompt()?
-                        .as_str(),
-                );
+            let handles = handles.clone();
+            self.who.push_str(
+                Text::new("Who are you:")
+                    .with_initial_value(current.as_str())
+                    .with_autocomplete(move |input: &str| {
+                        Ok(handles
+                            .iter()
+                            .filter(|handle| handle.starts_with(input))
+                            .cloned()
+                            .collect::<Vec<String>>())
+                    })
+                    .prompt()?
+                    .trim(),
+            );
+            if !self.flow.is_required(Step::Who) {
+                break;
             }
         }
-        if self.who.is_empty() {
+        if self.who.is_empty() && self.flow.is_required(Step::Who) {
             return Err(InquireError::from(Error::other("bad who")));
         }
         Ok(self)
     }
 
+    ///
+    /// Who else worked on this?
+    ///
+    /// # Errors
+    /// On bad user inputs
+    /// # Panics
+    /// if bad config
+    ///
+    pub fn ask_co_authors(&mut self) -> InquireResult<&mut Self> {
+        let config = load_config().breathes;
+        let me = self.me(&config);
+        let emails = my_emails(&config, me.as_str());
+        let current = std::mem::take(&mut self.co_authors);
+        let mut candidates = config
+            .team
+            .iter()
+            .filter(|member| !member.email.is_empty() && !is_me(member, &me, &emails))
+            .cloned()
+            .collect::<Vec<Member>>();
+        for author in history_authors().into_iter().chain(current.iter().cloned()) {
+            if !author.email.is_empty()
+                && !is_me(&author, &me, &emails)
+                && !candidates.iter().any(|member| {
+                    member.handle.eq(&author.handle)
+                        || member.email.eq_ignore_ascii_case(&author.email)
+                })
+            {
+                candidates.push(author);
+            }
+        }
+        let defaults = candidates
+            .iter()
+            .enumerate()
+            .filter(|(_, member)| {
+                current
+                    .iter()
+                    .any(|author| author.handle.eq(&member.handle))
+            })
+            .map(|(i, _)| i)
+            .collect::<Vec<usize>>();
+        let options = candidates
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<String>>();
+        let required = self.flow.is_required(Step::CoAuthors);
+        loop {
+            if !options.is_empty() {
+                for chosen in MultiSelect::new("Who else worked on this?", options.clone())
+                    .with_default(&defaults)
+                    .raw_prompt()?
+                {
+                    let mut author = candidates[chosen.index].clone();
+                    if let Some(previous) = current.iter().find(|a| a.handle.eq(&author.handle))
+                        && !previous.roles.is_empty()
+                    {
+                        author.roles.clone_from(&previous.roles);
+                    }
+                    self.co_authors.push(author);
+                }
+            }
+            while Confirm::new("Add someone else?")
+                .with_default(options.is_empty() && required && self.co_authors.is_empty())
+                .prompt()?
+            {
+                let name = Text::new("Name:").prompt()?;
+                let email = Text::new("Email:")
+                    .with_validator(EmailValidator)
+                    .prompt()?;
+                let handle = Text::new("Handle:")
+                    .with_initial_value(email.split('@').next().unwrap_or_default())
+                    .prompt()?;
+                let author = Member {
+                    handle: handle.trim().to_string(),
+                    name: name.trim().to_string(),
+                    email: email.trim().to_string(),
+                    roles: Vec::new(),
+                };
+                if is_me(&author, &me, &emails) {
+                    eprintln!("@{} is the author of the commit", author.handle);
+                    continue;
+                }
+                self.co_authors.push(author);
+            }
+            if !required || !self.co_authors.is_empty() {
+                break;
+            }
+        }
+        let roles = config
+            .roles()
+            .iter()
+            .map(|role| role.name.clone())
+            .collect::<Vec<String>>();
+        for author in &mut self.co_authors {
+            if author.roles.is_empty() {
+                author.roles = MultiSelect::new(
+                    format!("Select roles of @{}", author.handle).as_str(),
+                    roles.clone(),
+                )
+                .prompt()?;
+            }
+        }
+        Ok(self)
+    }
+
     ///
     /// What changes are you making?
     ///
@@ -489,16 +1605,20 @@ impl Commit {
     /// On bad user inputs
     ///
     pub fn ask_what(&mut self) -> InquireResult<&mut Self> {
-        self.what.clear();
+        let current = std::mem::take(&mut self.what);
         while self.what.is_empty() {
             self.what.clear();
             self.what.push_str(
                 Editor::new("What changes are you making?")
+                    .with_predefined_text(current.as_str())
                     .prompt()?
                     .as_str(),
             );
+            if !self.flow.is_required(Step::What) {
+                break;
+            }
         }
-        if self.what.is_empty() {
+        if self.what.is_empty() && self.flow.is_required(Step::What) {
             return Err(InquireError::from(Error::other("bad what")));
         }
         Ok(self)
@@ -512,16 +1632,20 @@ impl Commit {
     /// On bad user inputs
     ///
     pub fn ask_benefits(&mut self) -> InquireResult<&mut Self> {
-        self.benefits.clear();
+        let current = std::mem::take(&mut self.benefits);
         while self.benefits.is_empty() {
             self.benefits.clear();
             self.benefits.push_str(
                 Editor::new("What benefits does this change provide?")
+                    .with_predefined_text(current.as_str())
                     .prompt()?
                     .as_str(),
             );
+            if !self.flow.is_required(Step::Benefits) {
+                break;
+            }
         }
-        if self.benefits.is_empty() {
+        if self.benefits.is_empty() && self.flow.is_required(Step::Benefits) {
             return Err(InquireError::from(Error::other("bad benefits")));
         }
         Ok(self)
@@ -532,34 +1656,107 @@ impl Commit {
     /// # Errors
     /// On bad user inputs
     pub fn ask_resolves(&mut self) -> InquireResult<&mut Self> {
-        let mut issues: Vec<String> = Vec::new();
-        self.resolves.clear();
-        while issues.is_empty() {
-            issues.clear();
-            issues.push(Text::new("Issues number:").prompt()?);
-        }
-        self.resolves.append(&mut issues);
-        issues.clear();
-        if Confirm::new("Commit resolve more than one issue")
-            .with_default(false)
-            .prompt()?
-        {
-            loop {
-                while issues.is_empty() {
-                    issues.clear();
-                    issues.push(Text::new("Issues number:").prompt()?);
+        let required = self.flow.is_required(Step::Resolves);
+        let config = load_config().breathes;
+        let mut current = std::mem::take(&mut self.resolves);
+        if current.is_empty() {
+            current = branch_issues(config.branch.as_str());
+        }
+        let tracker: Option<Arc<dyn IssueProvider + Send + Sync>> = config
+            .issues
+            .as_ref()
+            .map(|issues| Arc::from(provider(issues)));
+        if let Some(tracker) = &tracker {
+            self.keyword = tracker.keyword().to_string();
+            match tracker.open_issues() {
+                Ok(issues) if !issues.is_empty() => {
+                    let mut options = current
+                        .iter()
+                        .filter(|id| !issues.iter().any(|issue| issue.id.eq(*id)))
+                        .cloned()
+                        .collect::<Vec<String>>();
+                    let mut defaults = (0..options.len()).collect::<Vec<usize>>();
+                    for issue in &issues {
+                        if current.contains(&issue.id) {
+                            defaults.push(options.len());
+                        }
+                        options.push(issue.to_string());
+                    }
+                    loop {
+                        self.resolves = MultiSelect::new("Issues resolved", options.clone())
+                            .with_default(&defaults)
+                            .prompt()?
+                            .iter()
+                            .filter_map(|issue| issue.split('~').next())
+                            .map(|id| id.trim().to_string())
+                            .collect();
+                        let known = Arc::clone(tracker);
+                        let others = Text::new("Other issues:")
+                            .with_help_message(
+                                "Ids missing from the list, separated by spaces, leave empty for none",
+                            )
+                            .with_validator(move |input: &str| {
+                                for id in input.split_whitespace() {
+                                    if let Ok(None) = known.issue(id) {
+                                        return Ok(Validation::Invalid(
+                                            format!("Unknown issue {id}").into(),
+                                        ));
+                                    }
+                                }
+                                Ok(Validation::Valid)
+                            })
+                            .prompt()?;
+                        for id in others.split_whitespace() {
+                            if !self.resolves.iter().any(|resolved| resolved.eq(id)) {
+                                self.resolves.push(id.to_string());
+                            }
+                        }
+                        if !required || !self.resolves.is_empty() {
+                            return Ok(self);
+                        }
+                    }
                 }
-                if Confirm::new("Add more issue?")
-                    .with_default(false)
-                    .prompt()?
-                {
-                    self.resolves.append(&mut issues);
-                } else {
-                    break;
+                Ok(_) => {}
+                Err(e) => eprintln!("failed to fetch issues: {e}"),
+            }
+        }
+        loop {
+            let mut text = Text::new("Issues number:")
+                .with_initial_value(current.get(self.resolves.len()).map_or("", String::as_str));
+            if !required {
+                text = text.with_help_message("Leave empty for no issue");
+            }
+            if let Some(tracker) = &tracker {
+                let tracker = Arc::clone(tracker);
+                text = text.with_validator(move |input: &str| {
+                    if input.trim().is_empty() {
+                        return Ok(Validation::Valid);
+                    }
+                    match tracker.issue(input.trim()) {
+                        Ok(Some(issue)) if issue.closed => {
+                            Ok(Validation::Invalid("This issue is closed".into()))
+                        }
+                        Ok(None) => Ok(Validation::Invalid("Unknown issue".into())),
+                        _ => Ok(Validation::Valid),
+                    }
+                });
+            }
+            let issue = text.prompt()?;
+            if issue.trim().is_empty() {
+                if required && self.resolves.is_empty() {
+                    continue;
                 }
+                break;
+            }
+            self.resolves.push(issue.trim().to_string());
+            if !Confirm::new("Add more issue?")
+                .with_default(self.resolves.len() < current.len())
+                .prompt()?
+            {
+                break;
             }
         }
-        if self.resolves.is_empty() {
+        if required && self.resolves.is_empty() {
             return Err(InquireError::from(Error::other("bad resolves")));
         }
         Ok(self)
@@ -575,22 +1772,70 @@ impl Commit {
     /// On bad user inputs
     ///
     pub fn ask_scopes(&mut self) -> InquireResult<&mut Self> {
-        self.scopes.clear();
         let mut scopes = Vec::new();
         let conf = load_config();
         for scope in &conf.breathes.scopes {
             scopes.push(scope.clone());
         }
+        if scopes.is_empty() {
+            return self.type_scopes();
+        }
+        let mut current = selected(&scopes, &std::mem::take(&mut self.scopes));
+        if !conf.breathes.paths.is_empty() {
+            let changed = if self.rewrite.is_empty() {
+                staged_files()
+            } else {
+                revision_files(self.rewrite.as_str())
+            };
+            let (matching, unmatched) = conf.breathes.scopes_of(&changed);
+            for file in &unmatched {
+                println!(
+                    "{} {}",
+                    "!".red(),
+                    format!("{file} matches no scope").yellow()
+                );
+            }
+            if current.is_empty() {
+                current = selected(&scopes, &matching);
+            }
+        }
         while self.scopes.is_empty() {
             self.scopes.clear();
-            self.scopes = MultiSelect::new("Select scopes", scopes.clone()).prompt()?;
+            self.scopes = MultiSelect::new("Select scopes", scopes.clone())
+                .with_default(&current)
+                .prompt()?;
+            if !self.flow.is_required(Step::Scopes) {
+                break;
+            }
         }
-        if self.scopes.is_empty() {
+        if self.scopes.is_empty() && self.flow.is_required(Step::Scopes) {
             return Err(InquireError::from(Error::other("bad scopes")));
         }
         Ok(self)
     }
 
+    fn type_scopes(&mut self) -> InquireResult<&mut Self> {
+        println!(
+            "{} {}",
+            "!".red(),
+            "no scopes in breath.yml, run `breath init` to discover them".yellow()
+        );
+        let current = std::mem::take(&mut self.scopes).join(",");
+        loop {
+            self.scopes = Text::new("Scopes (comma separated):")
+                .with_initial_value(current.as_str())
+                .prompt()?
+                .split(',')
+                .map(str::trim)
+                .filter(|scope| !scope.is_empty())
+                .map(String::from)
+                .collect();
+            if !self.scopes.is_empty() || !self.flow.is_required(Step::Scopes) {
+                return Ok(self);
+            }
+        }
+    }
+
     ///
     /// Ask for after changes
     ///
@@ -598,15 +1843,130 @@ impl Commit {
     /// On bad user inputs
     ///
     pub fn breaking_changes(&mut self) -> InquireResult<&mut Self> {
-        self.breaking_changes.clear();
+        let current = std::mem::take(&mut self.breaking_changes);
         while self.breaking_changes.is_empty() {
             self.breaking_changes.clear();
-            self.breaking_changes
-                .push_str(Editor::new("Breaking changes?").prompt()?.as_str());
+            self.breaking_changes.push_str(
+                Editor::new("Breaking changes?")
+                    .with_predefined_text(current.as_str())
+                    .prompt()?
+                    .as_str(),
+            );
+            if !self.flow.is_required(Step::BreakingChanges) {
+                break;
+            }
         }
-        if self.breaking_changes.is_empty() {
+        if self.breaking_changes.is_empty() && self.flow.is_required(Step::BreakingChanges) {
             return Err(InquireError::from(Error::other("bad breaking changes")));
         }
         Ok(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Commit, Style, branch_keys};
+    use crate::config::{Member, default_branch};
+    use std::str::FromStr;
+
+    fn commit(style: Style) -> Commit {
+        Commit {
+            t: String::from("feat"),
+            scopes: vec![String::from("core"), String::from("ui")],
+            summary: String::from("add the review loop"),
+            why: String::from("the wizard could not go back\nevery step was final"),
+            what: String::from("a review menu after the last step"),
+            breaking_changes: String::from("the draft format changed"),
+            who: String::from("alice"),
+            roles: vec![String::from("dev"), String::from("review")],
+            co_authors: vec![Member {
+                handle: String::from("bob"),
+                name: String::from("Bob Builder"),
+                email: String::from("bob@example.com"),
+                roles: vec![String::from("test")],
+            }],
+            notes: String::from("see the README"),
+            resolves: vec![String::from("42"), String::from("BR-7")],
+            keyword: String::from("Fixes #{id}"),
+            style,
+            ..Commit::default()
+        }
+    }
+
+    #[test]
+    fn round_trips_a_message() {
+        for style in [Style::Breath, Style::Conventional] {
+            let expected = commit(style);
+            let (parsed, strays) = Commit::parse(expected.to_string().as_str()).expect("parse");
+            assert!(strays.is_empty());
+            assert_eq!(parsed.style, style);
+            assert_eq!(parsed.t, expected.t);
+            assert_eq!(parsed.scopes, expected.scopes);
+            assert_eq!(parsed.summary, expected.summary);
+            assert_eq!(parsed.why, expected.why);
+            assert_eq!(parsed.what, expected.what);
+            assert_eq!(parsed.breaking_changes, expected.breaking_changes);
+            assert_eq!(parsed.notes, expected.notes);
+            assert_eq!(parsed.who, expected.who);
+            assert_eq!(parsed.roles, expected.roles);
+            assert_eq!(parsed.co_authors, expected.co_authors);
+            assert_eq!(parsed.resolves, expected.resolves);
+            assert_eq!(parsed.keyword, expected.keyword);
+            assert_eq!(parsed.to_string(), expected.to_string());
+            let edited = format!("{expected}\nkept outside any section\n");
+            let (_, strays) = Commit::parse(edited.as_str()).expect("parse");
+            assert_eq!(strays, vec!["kept outside any section"]);
+        }
+        assert!(Commit::from_str("Merge branch 'main'").is_err());
+    }
+
+    #[test]
+    fn finds_the_issue_keys_of_a_branch() {
+        let pattern = default_branch();
+        let keys = |name: &str| branch_keys(pattern.as_str(), name);
+        assert_eq!(keys("feature/1234-add-login"), ["1234"]);
+        assert_eq!(keys("fix/PROJ-88-crash"), ["PROJ-88"]);
+        assert_eq!(keys("fix/BR-7"), ["BR-7"]);
+        assert_eq!(keys("feature/issue-42"), ["42"]);
+        assert!(keys("release/2024").is_empty());
+        assert!(keys("release-2024-q1").is_empty());
+        assert!(keys("main").is_empty());
+    }
+
+    #[test]
+    fn renders_the_header_and_the_trailers() {
+        let mut commit = commit(Style::Conventional);
+        assert!(
+            commit
+                .to_string()
+                .starts_with("feat(core,ui)!: add the review loop\n")
+        );
+        commit.scopes.clear();
+        commit.breaking_changes.clear();
+        assert!(
+            commit
+                .to_string()
+                .starts_with("feat: add the review loop\n")
+        );
+        commit.style = Style::Breath;
+        assert!(
+            commit
+                .to_string()
+                .starts_with("feat() ~ add the review loop\n")
+        );
+        assert!(
+            commit
+                .to_string()
+                .contains("Co-authored-by: Bob Builder <bob@example.com>")
+        );
+        commit.co_authors[0].email.clear();
+        let message = commit.to_string();
+        assert!(message.contains("* @bob ~ test"));
+        assert!(!message.contains("Co-authored-by:"));
+        assert!(message.contains("\t\tFixes #BR-7\n"));
+        commit.keyword.clear();
+        let message = commit.to_string();
+        assert!(message.contains("\t\tFixes #42\n"));
+        assert!(message.contains("\t\tFixes BR-7\n"));
+    }
+}
@@ -0,0 +1,99 @@
+use crate::commit::vcs;
+use crate::config::try_load_config;
+use clap_complete::CompletionCandidate;
+use clap_complete::env::Shells;
+use std::io::{Error, Write};
+use std::process::Command;
+
+///
+/// The shells `breath gen completions` can register
+///
+pub const SHELLS: [&str; 5] = ["bash", "zsh", "fish", "elvish", "powershell"];
+
+///
+/// Complete the scopes of the configuration
+///
+#[must_use]
+pub fn scopes() -> Vec<CompletionCandidate> {
+    try_load_config()
+        .map(|config| {
+            config
+                .breathes
+                .scopes
+                .into_iter()
+                .map(CompletionCandidate::new)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+///
+/// Complete the commit types of the configuration, with their description
+///
+#[must_use]
+pub fn types() -> Vec<CompletionCandidate> {
+    try_load_config()
+        .map(|config| {
+            config
+                .breathes
+                .types
+                .iter()
+                .map(|t| {
+                    let (name, description) = t.split_once('~').unwrap_or((t.as_str(), ""));
+                    let description = description.trim();
+                    CompletionCandidate::new(name.trim())
+                        .help((!description.is_empty()).then(|| description.to_string().into()))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+///
+/// Complete the branches, and the bookmarks on mercurial
+///
+#[must_use]
+pub fn branches() -> Vec<CompletionCandidate> {
+    let outputs = if vcs().eq("hg") {
+        vec![
+            Command::new("hg")
+                .args(["branches", "-T", "{branch}\n"])
+                .output(),
+            Command::new("hg")
+                .args(["bookmarks", "-T", "{bookmark}\n"])
+                .output(),
+        ]
+    } else {
+        vec![
+            Command::new("git")
+                .args(["for-each-ref", "--format=%(refname:short)", "refs/heads"])
+                .output(),
+        ]
+    };
+    outputs
+        .into_iter()
+        .flatten()
+        .flat_map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(str::trim)
+                .filter(|branch| !branch.is_empty())
+                .map(String::from)
+                .collect::<Vec<String>>()
+        })
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+///
+/// Write the script registering the completions of breath in a shell
+///
+/// # Errors
+/// If the shell is unknown or the script cannot be written
+pub fn completions(shell: &str, out: &mut dyn Write) -> Result<(), Error> {
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(shell)
+        .ok_or_else(|| Error::other(format!("unsupported shell `{shell}`")))?;
+    completer.write_registration("COMPLETE", "breath", "breath", "breath", out)
+}
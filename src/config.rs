@@ -1,57 +1,935 @@
+use crate::commit;
+use crate::commit::Style;
+use crate::flow::{Flow, Step};
+use crate::issue::Provider;
+use crate::pipeline::{Entry, Task, default_allow};
+use crate::utils::breath_dir;
+use crate::zen::{ZenAction, default_zen};
+use crossterm::style::Stylize;
+use globset::{Glob, GlobSetBuilder};
+use inquire::Confirm;
+use regex::Regex;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
 use std::fs::File;
-use std::io::Write;
-#[derive(Deserialize, Serialize, Clone, Debug)]
+use std::io::{Error, ErrorKind, Write};
+use std::path::Path;
+///
+/// The version of the configuration layout written by this release
+///
+pub const CONFIG_VERSION: u32 = 2;
+
+///
+/// The JSON Schema written next to the configuration for the editors
+///
+pub const SCHEMA_PATH: &str = ".breath/schema.json";
+
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+#[serde(default)]
+#[doc = "The content of breath.yml"]
 pub struct BreathConfig {
+    /// The layout version, upgraded by `breath config migrate`
+    #[serde(default)]
+    pub version: u32,
+    /// The commit wizard settings
     pub breathes: Config,
+    /// The steps run by `breath gen doc` and `breath gen man`
     pub documentation: Documentation,
+    /// The actions of the `breath zen` menu
+    pub zen: Vec<ZenAction>,
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+impl Default for BreathConfig {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            breathes: Config::default(),
+            documentation: Documentation::default(),
+            zen: default_zen(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Clone, Debug)]
+#[serde(default)]
+#[doc = "The documentation and manual steps"]
 pub struct Documentation {
-    pub doc: Vec<String>,
-    pub man: Vec<String>,
+    /// Steps building the documentation
+    pub doc: Vec<Entry>,
+    /// Steps run after the man pages are generated
+    pub man: Vec<Entry>,
+    /// The directory receiving the generated man pages
+    pub man_dir: String,
+    /// The programs the steps may run
+    pub allow: Vec<String>,
 }
-#[derive(Deserialize, Serialize, Debug, Clone)]
+
+impl Default for Documentation {
+    fn default() -> Self {
+        Self {
+            doc: vec![],
+            man: vec![],
+            man_dir: String::from("man"),
+            allow: default_allow(),
+        }
+    }
+}
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone)]
+#[serde(default)]
+#[doc = "The commit wizard settings"]
 pub struct Config {
+    /// The scopes a commit can touch
     pub scopes: Vec<String>,
+    /// The commit types, optionally followed by `~ description`
     pub types: Vec<String>,
+    /// The header style of the commit message
+    pub style: Style,
+    /// The sections every commit type must answer
+    pub required: Vec<Step>,
+    /// The questions asked for each commit type
+    pub flows: BTreeMap<String, Flow>,
+    /// The issue tracker checked by the resolves step
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issues: Option<Issues>,
+    /// The regex extracting an issue id from the branch name
+    pub branch: String,
+    /// The roles of the team
+    pub roles: Vec<Role>,
+    /// The members of the team
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub team: Vec<Member>,
+    /// The path globs of each scope
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub paths: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            scopes: vec![],
+            types: default_types(),
+            style: Style::default(),
+            required: default_required(),
+            flows: Flow::defaults(),
+            issues: None,
+            branch: default_branch(),
+            roles: builtin_roles(),
+            team: vec![],
+            paths: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+pub struct Member {
+    pub handle: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub email: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+}
+
+impl Display for Member {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "@{} ~ {} <{}>", self.handle, self.name, self.email)
+    }
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+pub struct Role {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub users: Vec<String>,
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.description.is_empty() {
+            write!(f, "{}", self.name)
+        } else {
+            write!(f, "{} ~ {}", self.name, self.description)
+        }
+    }
+}
+
+#[must_use]
+pub fn default_branch() -> String {
+    String::from(
+        r"(?:^|[/_-])([A-Z][A-Z0-9]+-\d+)(?:[/_-]|$)|(?:^|/)(\d+)[_-][A-Za-z]|(?:^|[/_-])(?:issue|gh)-?(\d+)(?:[/_-]|$)",
+    )
+}
+
+#[must_use]
+pub fn default_types() -> Vec<String> {
+    [
+        "feat", "chore", "fix", "docs", "style", "refactor", "perf", "test", "build", "ci",
+        "revert", "release", "bump",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+#[must_use]
+pub fn default_required() -> Vec<Step> {
+    Step::all()
+        .into_iter()
+        .filter(|step| step.required_by_default())
+        .collect()
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone)]
+pub struct Issues {
+    pub provider: Provider,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub project: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+}
+
+impl Config {
+    ///
+    /// The flow of a commit type, falling back to the built-in one
+    ///
+    /// Steps the flow does not mark required or optional follow the `required` sections.
+    ///
+    #[must_use]
+    pub fn flow(&self, t: &str) -> Flow {
+        let mut flow = self
+            .flows
+            .get(t)
+            .cloned()
+            .unwrap_or_else(|| Flow::builtin(t));
+        for step in Step::all() {
+            if flow.require.contains(&step) || flow.optional.contains(&step) {
+                continue;
+            }
+            let required = self.required.contains(&step);
+            if required && !step.required_by_default() {
+                flow.require.push(step);
+            } else if !required && step.required_by_default() {
+                flow.optional.push(step);
+            }
+        }
+        flow
+    }
+
+    ///
+    /// Check that every path glob of the scopes compiles
+    ///
+    /// # Errors
+    /// On the first invalid glob, naming its scope
+    pub fn check_paths(&self) -> Result<(), Error> {
+        for (scope, patterns) in &self.paths {
+            for pattern in patterns {
+                Glob::new(pattern).map_err(|e| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("invalid path glob `{pattern}` of scope `{scope}`: {e}"),
+                    )
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// The scopes whose path globs match the files, and the files matching no scope
+    ///
+    /// Invalid globs match nothing, `try_load_config` rejects them beforehand.
+    ///
+    #[must_use]
+    pub fn scopes_of(&self, files: &[String]) -> (Vec<String>, Vec<String>) {
+        let mut globs = Vec::new();
+        for (scope, patterns) in &self.paths {
+            let mut builder = GlobSetBuilder::new();
+            for pattern in patterns {
+                if let Ok(glob) = Glob::new(pattern) {
+                    builder.add(glob);
+                }
+            }
+            if let Ok(set) = builder.build() {
+                globs.push((scope, set));
+            }
+        }
+        let mut scopes = Vec::new();
+        let mut unmatched = Vec::new();
+        for file in files {
+            let mut matched = false;
+            for (scope, set) in &globs {
+                if set.is_match(file) {
+                    matched = true;
+                    if !scopes.contains(*scope) {
+                        scopes.push((*scope).clone());
+                    }
+                }
+            }
+            if !matched {
+                unmatched.push(file.clone());
+            }
+        }
+        (scopes, unmatched)
+    }
+
+    ///
+    /// The roles of the team, falling back to the built-in ones
+    ///
+    #[must_use]
+    pub fn roles(&self) -> Vec<Role> {
+        if self.roles.is_empty() {
+            builtin_roles()
+        } else {
+            self.roles.clone()
+        }
+    }
+}
+
+#[must_use]
+pub fn builtin_roles() -> Vec<Role> {
+    commit::Role::all()
+        .iter()
+        .map(|role| Role {
+            name: role.to_string(),
+            description: String::new(),
+            users: Vec::new(),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[doc = "A file breath reads its configuration from"]
+pub enum Source {
+    Yaml,
+    Toml,
+    Json,
+    Cargo,
 }
 
+///
+/// The span of the numeric `version` entry of the top-level JSON object, skipping nested objects and strings
+///
+fn json_version(content: &str) -> Result<Option<std::ops::Range<usize>>, Error> {
+    let value = Regex::new(r"^\s*:\s*\d+").map_err(Error::other)?;
+    let bytes = content.as_bytes();
+    let mut depth = 0_usize;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            b'"' => {
+                let start = i;
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                if depth == 1
+                    && content.get(start..=i) == Some("\"version\"")
+                    && let Some(found) = value.find(&content[i + 1..])
+                {
+                    return Ok(Some(start..i + 1 + found.end()));
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    Ok(None)
+}
+
+impl Source {
+    ///
+    /// The sources in their order of precedence
+    ///
+    #[must_use]
+    pub const fn all() -> [Self; 4] {
+        [Self::Yaml, Self::Toml, Self::Json, Self::Cargo]
+    }
+
+    #[must_use]
+    pub const fn path(self) -> &'static str {
+        match self {
+            Self::Yaml => "breath.yml",
+            Self::Toml => "breath.toml",
+            Self::Json => ".breath.json",
+            Self::Cargo => "Cargo.toml",
+        }
+    }
+
+    fn cargo_table(content: &str) -> Option<toml::Value> {
+        let manifest = content.parse::<toml::Table>().ok()?;
+        ["package", "workspace"].into_iter().find_map(|section| {
+            manifest
+                .get(section)
+                .and_then(|section| section.get("metadata"))
+                .and_then(|metadata| metadata.get("breath"))
+                .cloned()
+        })
+    }
+
+    fn is_present(self) -> bool {
+        match self {
+            Self::Cargo => std::fs::read_to_string(self.path())
+                .is_ok_and(|content| Self::cargo_table(&content).is_some()),
+            Self::Yaml | Self::Toml | Self::Json => Path::new(self.path()).is_file(),
+        }
+    }
+
+    ///
+    /// The text of the file, and the configuration it holds as a generic value
+    ///
+    fn read(self) -> Result<(String, serde_json::Value), Error> {
+        let content = std::fs::read_to_string(self.path())?;
+        let value = self.parse(content.as_str())?;
+        Ok((content, value))
+    }
+
+    ///
+    /// The configuration held by the text of the file, as a generic value
+    ///
+    fn parse(self, content: &str) -> Result<serde_json::Value, Error> {
+        match self {
+            Self::Yaml => serde_yaml::from_str(content).map_err(Error::other),
+            Self::Toml => toml::from_str(content).map_err(Error::other),
+            Self::Json => serde_json::from_str(content).map_err(Error::other),
+            Self::Cargo => {
+                let table = Self::cargo_table(content)
+                    .ok_or_else(|| Error::other("no breath metadata in Cargo.toml"))?;
+                serde_json::to_value(&table).map_err(Error::other)
+            }
+        }
+    }
+
+    ///
+    /// Set the `version` key in the text of the file, inserting it at the top when missing
+    ///
+    fn set_version(self, content: &str) -> Result<String, Error> {
+        let version = match self {
+            Self::Yaml => format!("version: {CONFIG_VERSION}"),
+            Self::Toml | Self::Cargo => format!("version = {CONFIG_VERSION}"),
+            Self::Json => format!("\"version\": {CONFIG_VERSION}"),
+        };
+        let (start, end) = match self {
+            Self::Yaml | Self::Json => (0, content.len()),
+            Self::Toml => (
+                0,
+                Regex::new(r"(?m)^\[")
+                    .map_err(Error::other)?
+                    .find(content)
+                    .map_or(content.len(), |table| table.start()),
+            ),
+            Self::Cargo => {
+                let header =
+                    Regex::new(r"(?m)^\[(?:package|workspace)\.metadata\.breath\][ \t]*\r?\n")
+                        .map_err(Error::other)?
+                        .find(content)
+                        .ok_or_else(|| {
+                            Error::other(
+                                "add `version` to the breath metadata of Cargo.toml by hand",
+                            )
+                        })?
+                        .end();
+                let end = content[header..]
+                    .find("\n[")
+                    .map_or(content.len(), |next| header + next + 1);
+                (header, end)
+            }
+        };
+        let section = &content[start..end];
+        let found = match self {
+            Self::Yaml => Regex::new(r"(?m)^version[ \t]*:.*$")
+                .map_err(Error::other)?
+                .find(section)
+                .map(|found| found.range()),
+            Self::Toml | Self::Cargo => Regex::new(r"(?m)^version[ \t]*=.*$")
+                .map_err(Error::other)?
+                .find(section)
+                .map(|found| found.range()),
+            Self::Json => json_version(section)?,
+        };
+        if let Some(found) = found {
+            return Ok(format!(
+                "{}{version}{}",
+                &content[..start + found.start],
+                &content[start + found.end..]
+            ));
+        }
+        let at = match self {
+            Self::Json => {
+                let open = content
+                    .find('{')
+                    .ok_or_else(|| Error::other("the configuration is not a JSON object"))?
+                    + 1;
+                let rest = &content[open..];
+                let indent = rest
+                    .trim_start_matches(['\r', '\n'])
+                    .chars()
+                    .take_while(|c| c.eq(&' ') || c.eq(&'\t'))
+                    .collect::<String>();
+                let entry = if rest.trim_start().starts_with('}') {
+                    version
+                } else if rest.starts_with(['\r', '\n']) {
+                    format!("\n{indent}{version},")
+                } else {
+                    format!("{version}, ")
+                };
+                return Ok(format!("{}{entry}{rest}", &content[..open]));
+            }
+            Self::Cargo => start,
+            Self::Yaml | Self::Toml => {
+                let mut at = 0;
+                for line in content.split_inclusive('\n') {
+                    let trimmed = line.trim();
+                    if !trimmed.is_empty() && !trimmed.starts_with('#') && trimmed.ne("---") {
+                        break;
+                    }
+                    at += line.len();
+                }
+                at
+            }
+        };
+        let newline = if at == content.len() && !content.is_empty() && !content.ends_with('\n') {
+            "\n"
+        } else {
+            ""
+        };
+        Ok(format!(
+            "{}{newline}{version}\n{}",
+            &content[..at],
+            &content[at..]
+        ))
+    }
+
+    ///
+    /// Replace a command line step by a structured one in the text of the file, in the inline form of its format
+    ///
+    fn replace_step(self, content: &str, line: &str, task: &Task) -> Result<String, Error> {
+        let quote = |text: &str| serde_json::to_string(text).map_err(Error::other);
+        let list = |items: &[String]| -> Result<String, Error> {
+            Ok(format!(
+                "[{}]",
+                items
+                    .iter()
+                    .map(|item| quote(item))
+                    .collect::<Result<Vec<String>, Error>>()?
+                    .join(", ")
+            ))
+        };
+        let fields = [
+            ("program", quote(task.program.as_str())?),
+            ("args", list(&task.args)?),
+            ("outputs", list(&task.outputs)?),
+        ];
+        let inline = fields
+            .iter()
+            .map(|(key, value)| match self {
+                Self::Yaml => format!("{key}: {value}"),
+                Self::Json => format!("\"{key}\": {value}"),
+                Self::Toml | Self::Cargo => format!("{key} = {value}"),
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+        let inline = format!("{{ {inline} }}");
+        let mut new = content.to_string();
+        for literal in [quote(line)?, format!("'{line}'")] {
+            new = new.replace(literal.as_str(), inline.as_str());
+        }
+        if self.eq(&Self::Yaml) {
+            let plain = Regex::new(
+                format!(
+                    r"(?m)(^[ \t]*-[ \t]+|[\[,][ \t]*){}([ \t]*(?:#.*)?$|[ \t]*[,\]])",
+                    regex::escape(line)
+                )
+                .as_str(),
+            )
+            .map_err(Error::other)?;
+            new = plain
+                .replace_all(new.as_str(), |caps: &regex::Captures| {
+                    format!("{}{inline}{}", &caps[1], &caps[2])
+                })
+                .to_string();
+        }
+        Ok(new)
+    }
+
+    fn render(self, config: &BreathConfig) -> Result<String, Error> {
+        match self {
+            Self::Yaml => Ok(format!(
+                "# yaml-language-server: $schema={SCHEMA_PATH}\n{}",
+                serde_yaml::to_string(config).map_err(Error::other)?
+            )),
+            Self::Toml => Ok(format!(
+                "#:schema {SCHEMA_PATH}\n{}",
+                toml::to_string(config).map_err(Error::other)?
+            )),
+            Self::Json => Ok(format!(
+                "{}\n",
+                serde_json::to_string_pretty(config).map_err(Error::other)?
+            )),
+            Self::Cargo => toml::to_string(config).map_err(Error::other),
+        }
+    }
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cargo => write!(f, "Cargo.toml metadata"),
+            Self::Yaml | Self::Toml | Self::Json => write!(f, "{}", self.path()),
+        }
+    }
+}
+
+///
+/// The file holding the configuration, if any
+///
+/// # Errors
+/// If more than one file configures breath
+pub fn config_source() -> Result<Option<Source>, Error> {
+    let found = Source::all()
+        .into_iter()
+        .filter(|source| source.is_present())
+        .collect::<Vec<Source>>();
+    if found.len() > 1 {
+        return Err(Error::other(format!(
+            "breath is configured in {}, keep only one",
+            found
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<String>>()
+                .join(" and ")
+        )));
+    }
+    Ok(found.first().copied())
+}
+
+///
+/// Read the configuration from breath.yml, breath.toml, .breath.json or the Cargo.toml metadata
+///
+/// # Errors
+/// If no file or more than one configures breath, or on a syntax error
+pub fn try_load_config() -> Result<BreathConfig, Error> {
+    let source = config_source()?.ok_or_else(|| {
+        Error::new(
+            ErrorKind::NotFound,
+            "no breath configuration, run `breath init`",
+        )
+    })?;
+    let (_, value) = source.read()?;
+    let config: BreathConfig = serde_json::from_value(value)
+        .map_err(|e| Error::other(format!("syntax error in {source}: {e}")))?;
+    if config.version > CONFIG_VERSION {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "{source} has version {}, this breath only knows up to {CONFIG_VERSION}, upgrade breath",
+                config.version
+            ),
+        ));
+    }
+    config
+        .breathes
+        .check_paths()
+        .map_err(|e| Error::new(e.kind(), format!("{source}: {e}")))?;
+    Ok(config)
+}
+
+///
+/// Read the configuration
+///
+/// # Panics
+/// If the configuration is missing, ambiguous or invalid
+#[must_use]
 pub fn load_config() -> BreathConfig {
-    let content = std::fs::read_to_string("breath.yml").expect("failed to read breath.yml");
-    serde_yaml::from_str(&content).expect("syntax error in breath.yml")
+    try_load_config().unwrap_or_else(|e| panic!("{e}"))
 }
-pub fn init_config() -> Result<(), serde_yaml::Error> {
-    let config = BreathConfig {
-        breathes: Config {
-            scopes: vec![],
-            types: vec![
-                String::from("feat"),
-                String::from("chore"),
-                String::from("fix"),
-                String::from("docs"),
-                String::from("style"),
-                String::from("refactor"),
-                String::from("perf"),
-                String::from("test"),
-                String::from("build"),
-                String::from("ci"),
-                String::from("revert"),
-                String::from("release"),
-                String::from("bump"),
-            ],
-        },
-        documentation: Documentation {
-            doc: vec![],
-            man: vec![],
-        },
-    };
-    let config = serde_yaml::to_string(&config)?;
-    let config_path = std::path::Path::new("breath.yml");
-    let mut f = File::create(config_path).expect("failed to create breath.yml");
-    f.write_all(config.as_bytes())
-        .expect("failed to write breath.yml");
-    f.sync_all().expect("failed to sync breath.yml");
+
+///
+/// The JSON Schema of the configuration
+///
+/// # Errors
+/// If the schema cannot be serialized
+pub fn schema() -> Result<String, Error> {
+    serde_json::to_string_pretty(&schemars::schema_for!(BreathConfig)).map_err(Error::other)
+}
+
+///
+/// Write the configuration to its file, breath.yml by default, with its JSON Schema for the editors
+///
+/// # Errors
+/// If the file already exists without `force`, lives in Cargo.toml, or cannot be written
+pub fn write_config(config: &BreathConfig, force: bool) -> Result<(), Error> {
+    let source = config_source()?;
+    if let Some(source) = source
+        && !force
+    {
+        return Err(Error::new(
+            ErrorKind::AlreadyExists,
+            format!("{source} already exists, use --force to overwrite it"),
+        ));
+    }
+    let source = source.unwrap_or(Source::Yaml);
+    if source.eq(&Source::Cargo) {
+        return Err(Error::other(
+            "breath does not rewrite Cargo.toml, update [package.metadata.breath] by hand",
+        ));
+    }
+    let content = source.render(config)?;
+    breath_dir()?;
+    std::fs::write(SCHEMA_PATH, schema()?)?;
+    let mut f = File::create(source.path())?;
+    f.write_all(content.as_bytes())?;
+    f.sync_all()
+}
+
+///
+/// Upgrade the configuration to the current layout, showing the changes before writing them
+///
+/// # Errors
+/// If the configuration cannot be read, parsed or written, or was written by a newer breath
+pub fn migrate(yes: bool) -> Result<(), Error> {
+    let source = config_source()?.ok_or_else(|| {
+        Error::new(
+            ErrorKind::NotFound,
+            "no breath configuration, run `breath init`",
+        )
+    })?;
+    let (old, value) = source.read()?;
+    let version = value
+        .get("version")
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or_default();
+    if version > u64::from(CONFIG_VERSION) {
+        return Err(Error::other(format!(
+            "{source} has version {version}, this breath only knows up to {CONFIG_VERSION}"
+        )));
+    }
+    let new = upgrade(source, old.as_str(), version)?;
+    if new.eq(&old) {
+        println!(
+            "{} {}",
+            "✓".green(),
+            format!("{source} is up to date").dark_cyan()
+        );
+        return Ok(());
+    }
+    for change in TextDiff::from_lines(old.as_str(), new.as_str()).iter_all_changes() {
+        let line = change.to_string_lossy();
+        let line = line.trim_end_matches('\n');
+        match change.tag() {
+            ChangeTag::Delete => println!("{}", format!("-{line}").red()),
+            ChangeTag::Insert => println!("{}", format!("+{line}").green()),
+            ChangeTag::Equal => println!(" {line}"),
+        }
+    }
+    if source.eq(&Source::Cargo) {
+        println!(
+            "{}",
+            "breath does not rewrite Cargo.toml, apply this diff to its breath metadata by hand"
+                .yellow()
+        );
+        return Ok(());
+    }
+    if !yes
+        && !Confirm::new(format!("Migrate {source} to version {CONFIG_VERSION}?").as_str())
+            .with_default(false)
+            .prompt()
+            .map_err(Error::other)?
+    {
+        return Ok(());
+    }
+    breath_dir()?;
+    std::fs::write(SCHEMA_PATH, schema()?)?;
+    std::fs::write(source.path(), new)?;
+    println!(
+        "{} {}",
+        "✓".green(),
+        format!("{source} migrated to version {CONFIG_VERSION}").dark_cyan()
+    );
     Ok(())
 }
+
+///
+/// Bring the text of a configuration to the current version, keeping its comments and layout
+///
+/// Only the `version` key and the entries whose layout changed are edited, and the result must still load:
+///
+/// - version 1 adds the `version` key;
+/// - version 2 turns the command lines running `rm`, `cp`, `mv`, `mkdir`, `touch` or `ln` into steps declaring the
+///   paths they write as `outputs`.
+///
+fn upgrade(source: Source, content: &str, version: u64) -> Result<String, Error> {
+    let mut new = source.set_version(content)?;
+    if version < 2 {
+        let config: BreathConfig =
+            serde_json::from_value(source.parse(new.as_str())?).map_err(Error::other)?;
+        for entry in config
+            .documentation
+            .doc
+            .iter()
+            .chain(&config.documentation.man)
+        {
+            if let (Entry::Line(line), Some(task)) = (entry, entry.with_outputs()) {
+                new = source.replace_step(new.as_str(), line, &task)?;
+            }
+        }
+    }
+    let config: BreathConfig = serde_json::from_value(source.parse(new.as_str())?)
+        .map_err(|e| Error::other(format!("the migrated {source} does not load: {e}")))?;
+    if let Some(line) = config
+        .documentation
+        .doc
+        .iter()
+        .chain(&config.documentation.man)
+        .find(|entry| matches!(entry, Entry::Line(_)) && entry.task().is_writer())
+    {
+        return Err(Error::other(format!(
+            "turn `{line}` into a step with its program, args and outputs by hand"
+        )));
+    }
+    Ok(new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CONFIG_VERSION, Config, Source, upgrade};
+
+    fn config(paths: &[(&str, &[&str])]) -> Config {
+        let mut config = Config::default();
+        for (scope, patterns) in paths {
+            config.paths.insert(
+                (*scope).to_string(),
+                patterns.iter().map(ToString::to_string).collect(),
+            );
+        }
+        config
+    }
+
+    fn files(files: &[&str]) -> Vec<String> {
+        files.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn maps_the_files_to_their_scopes() {
+        let project = config(&[
+            ("core", &["src/**"]),
+            ("docs", &["*.md", "docs/**"]),
+            ("ci", &[".github/**"]),
+        ]);
+        let (scopes, unmatched) = project.scopes_of(&files(&[
+            "src/main.rs",
+            "README.md",
+            "docs/a.md",
+            "Cargo.toml",
+        ]));
+        assert_eq!(scopes, ["core", "docs"]);
+        assert_eq!(unmatched, ["Cargo.toml"]);
+        let overlapping = config(&[("core", &["src/**"]), ("lint", &["src/lint.rs"])]);
+        let (scopes, unmatched) = overlapping.scopes_of(&files(&["src/lint.rs"]));
+        assert_eq!(scopes, ["core", "lint"]);
+        assert!(unmatched.is_empty());
+    }
+
+    #[test]
+    fn rejects_an_invalid_glob() {
+        assert!(config(&[("core", &["src/**"])]).check_paths().is_ok());
+        let error = config(&[("core", &["src/[a"])])
+            .check_paths()
+            .expect_err("invalid glob");
+        assert!(error.to_string().contains("`src/[a` of scope `core`"));
+    }
+
+    #[test]
+    fn writes_the_version_in_every_format() {
+        let old = "# yaml-language-server: $schema=.breath/schema.json\n# our settings\nbreathes:\n  scopes: [ core ] # the crate\n";
+        let new = upgrade(Source::Yaml, old, 0).expect("upgrade");
+        assert_eq!(
+            new,
+            format!(
+                "# yaml-language-server: $schema=.breath/schema.json\n# our settings\nversion: {CONFIG_VERSION}\nbreathes:\n  scopes: [ core ] # the crate\n"
+            )
+        );
+        assert_eq!(
+            upgrade(Source::Yaml, new.as_str(), 2).expect("upgrade"),
+            new
+        );
+        assert_eq!(
+            upgrade(Source::Yaml, "version: 0\nzen: []\n", 0).expect("upgrade"),
+            format!("version: {CONFIG_VERSION}\nzen: []\n")
+        );
+        let old = "#:schema .breath/schema.json\n[breathes]\nscopes = [\"core\"]\n\n[[zen]]\nlabel = \"Version\"\nkey = \"v\"\ncommand = [\"cargo\", \"--version\"]\nversion = 3\n";
+        assert_eq!(
+            upgrade(Source::Toml, old, 0).expect("upgrade"),
+            format!(
+                "#:schema .breath/schema.json\nversion = {CONFIG_VERSION}\n[breathes]\nscopes = [\"core\"]\n\n[[zen]]\nlabel = \"Version\"\nkey = \"v\"\ncommand = [\"cargo\", \"--version\"]\nversion = 3\n"
+            )
+        );
+        let old = "{\n  \"breathes\": {\n    \"scopes\": []\n  }\n}\n";
+        assert_eq!(
+            upgrade(Source::Json, old, 0).expect("upgrade"),
+            format!(
+                "{{\n  \"version\": {CONFIG_VERSION},\n  \"breathes\": {{\n    \"scopes\": []\n  }}\n}}\n"
+            )
+        );
+        assert_eq!(
+            upgrade(Source::Json, "{}", 0).expect("upgrade"),
+            format!("{{\"version\": {CONFIG_VERSION}}}")
+        );
+        let old = "{\"zen\": [{\"label\": \"version\", \"key\": \"v\", \"command\": [\"cargo\"], \"version\": 3}], \"version\": 1}";
+        assert_eq!(
+            upgrade(Source::Json, old, 1).expect("upgrade"),
+            format!(
+                "{{\"zen\": [{{\"label\": \"version\", \"key\": \"v\", \"command\": [\"cargo\"], \"version\": 3}}], \"version\": {CONFIG_VERSION}}}"
+            )
+        );
+        let old = "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n\n[package.metadata.breath]\nzen = []\n\n[dependencies]\n";
+        assert_eq!(
+            upgrade(Source::Cargo, old, 0).expect("upgrade"),
+            format!(
+                "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n\n[package.metadata.breath]\nversion = {CONFIG_VERSION}\nzen = []\n\n[dependencies]\n"
+            )
+        );
+    }
+
+    #[test]
+    fn migrates_the_writing_command_lines() {
+        let old = "documentation:\n  doc:\n    - cargo doc --no-deps\n    - cp -r target/doc docs # publish\n    - \"touch docs/.nojekyll\"\n";
+        assert_eq!(
+            upgrade(Source::Yaml, old, 1).expect("upgrade"),
+            format!(
+                "version: {CONFIG_VERSION}\ndocumentation:\n  doc:\n    - cargo doc --no-deps\n    - {{ program: \"cp\", args: [\"-r\", \"target/doc\", \"docs\"], outputs: [\"docs\"] }} # publish\n    - {{ program: \"touch\", args: [\"docs/.nojekyll\"], outputs: [\"docs/.nojekyll\"] }}\n"
+            )
+        );
+        let old = "[documentation]\nman = [\"mkdir -p man/gz\"]\n";
+        assert_eq!(
+            upgrade(Source::Toml, old, 1).expect("upgrade"),
+            format!(
+                "version = {CONFIG_VERSION}\n[documentation]\nman = [{{ program = \"mkdir\", args = [\"-p\", \"man/gz\"], outputs = [\"man/gz\"] }}]\n"
+            )
+        );
+        let old = "{\"documentation\": {\"doc\": [\"rm -rf docs\"]}}";
+        assert_eq!(
+            upgrade(Source::Json, old, 1).expect("upgrade"),
+            format!(
+                "{{\"version\": {CONFIG_VERSION}, \"documentation\": {{\"doc\": [{{ \"program\": \"rm\", \"args\": [\"-rf\", \"docs\"], \"outputs\": [\"docs\"] }}]}}}}"
+            )
+        );
+        let old = "documentation:\n  doc:\n    - cp -t docs README.md\n";
+        let error = upgrade(Source::Yaml, old, 1).expect_err("target directory");
+        assert!(error.to_string().contains("`cp -t docs README.md`"));
+    }
+}
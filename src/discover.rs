@@ -0,0 +1,233 @@
+use globset::GlobBuilder;
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[doc = "A scope found in the layout of the repository"]
+pub struct Candidate {
+    pub scope: String,
+    pub path: String,
+    pub source: &'static str,
+}
+
+impl Display for Candidate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ~ {} ({})", self.scope, self.path, self.source)
+    }
+}
+
+fn directories() -> Vec<String> {
+    let mut dirs = ignore::WalkBuilder::new(".")
+        .add_custom_ignore_filename(".breathing")
+        .standard_filters(true)
+        .max_depth(Some(4))
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_dir()))
+        .filter_map(|entry| {
+            entry
+                .path()
+                .strip_prefix(".")
+                .ok()
+                .and_then(Path::to_str)
+                .map(String::from)
+        })
+        .filter(|dir| !dir.is_empty())
+        .collect::<Vec<String>>();
+    dirs.sort();
+    dirs
+}
+
+fn expand(pattern: &str, dirs: &[String]) -> Vec<String> {
+    let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+    if !pattern.contains('*') {
+        return vec![pattern.to_string()];
+    }
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map_or_else(
+            |_| Vec::new(),
+            |glob| {
+                let matcher = glob.compile_matcher();
+                dirs.iter()
+                    .filter(|dir| matcher.is_match(dir.as_str()))
+                    .cloned()
+                    .collect()
+            },
+        )
+}
+
+fn candidates(paths: Vec<String>, source: &'static str) -> Vec<Candidate> {
+    paths
+        .into_iter()
+        .filter(|path| !path.is_empty() && Path::new(path).is_dir())
+        .map(|path| Candidate {
+            scope: path.rsplit('/').next().unwrap_or(path.as_str()).to_string(),
+            path,
+            source,
+        })
+        .collect()
+}
+
+fn cargo_members(dirs: &[String]) -> Vec<Candidate> {
+    let Ok(content) = std::fs::read_to_string("Cargo.toml") else {
+        return Vec::new();
+    };
+    let Ok(manifest) = content.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+    let members = manifest
+        .get("workspace")
+        .and_then(|workspace| workspace.get("members"))
+        .and_then(|members| members.as_array())
+        .map(|members| {
+            members
+                .iter()
+                .filter_map(|member| member.as_str())
+                .flat_map(|member| expand(member, dirs))
+                .collect()
+        })
+        .unwrap_or_default();
+    candidates(members, "cargo workspace")
+}
+
+fn node_workspaces(dirs: &[String]) -> Vec<Candidate> {
+    let Ok(content) = std::fs::read_to_string("package.json") else {
+        return Vec::new();
+    };
+    let Ok(package) = serde_json::from_str::<Value>(&content) else {
+        return Vec::new();
+    };
+    let workspaces = match &package["workspaces"] {
+        Value::Array(workspaces) => workspaces.clone(),
+        Value::Object(workspaces) => workspaces
+            .get("packages")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    let members = workspaces
+        .iter()
+        .filter_map(Value::as_str)
+        .flat_map(|workspace| expand(workspace, dirs))
+        .collect();
+    candidates(members, "package.json workspaces")
+}
+
+fn go_modules(dirs: &[String]) -> Vec<Candidate> {
+    let modules = dirs
+        .iter()
+        .filter(|dir| Path::new(dir.as_str()).join("go.mod").is_file())
+        .cloned()
+        .collect();
+    candidates(modules, "go module")
+}
+
+fn src_directories() -> Vec<Candidate> {
+    let Ok(entries) = std::fs::read_dir("src") else {
+        return Vec::new();
+    };
+    let mut dirs = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .filter_map(|entry| entry.file_name().to_str().map(|name| format!("src/{name}")))
+        .collect::<Vec<String>>();
+    dirs.sort();
+    candidates(dirs, "src directory")
+}
+
+///
+/// Drop the paths found twice and name the scopes sharing a directory name after their parents
+///
+/// `crates/core` and `web/core` become `crates-core` and `web-core`, and the whole path is used when the parents
+/// collide too.
+///
+fn unique(candidates: Vec<Candidate>) -> Vec<Candidate> {
+    let mut found: Vec<Candidate> = Vec::new();
+    for candidate in candidates {
+        if !found.iter().any(|c| c.path.eq(&candidate.path)) {
+            found.push(candidate);
+        }
+    }
+    let named = |path: &str, depth: usize| {
+        let parts = path.split('/').collect::<Vec<&str>>();
+        parts[parts.len().saturating_sub(depth)..].join("-")
+    };
+    for depth in [2, usize::MAX] {
+        let scopes = found
+            .iter()
+            .map(|c| c.scope.clone())
+            .collect::<Vec<String>>();
+        for candidate in &mut found {
+            if scopes.iter().filter(|s| s.eq(&&candidate.scope)).count() > 1 {
+                candidate.scope = named(candidate.path.as_str(), depth);
+            }
+        }
+    }
+    found
+}
+
+///
+/// Discover candidate scopes from workspace members, go modules and `src/` subdirectories
+///
+#[must_use]
+pub fn discover() -> Vec<Candidate> {
+    let dirs = directories();
+    unique(
+        cargo_members(&dirs)
+            .into_iter()
+            .chain(node_workspaces(&dirs))
+            .chain(go_modules(&dirs))
+            .chain(src_directories())
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Candidate, expand, unique};
+
+    fn candidate(path: &str) -> Candidate {
+        Candidate {
+            scope: path.rsplit('/').next().unwrap_or(path).to_string(),
+            path: path.to_string(),
+            source: "test",
+        }
+    }
+
+    fn scopes(candidates: &[Candidate]) -> Vec<&str> {
+        candidates.iter().map(|c| c.scope.as_str()).collect()
+    }
+
+    #[test]
+    fn expands_the_member_globs() {
+        let dirs = [
+            "crates",
+            "crates/core",
+            "crates/core/src",
+            "crates/ui",
+            "web",
+        ]
+        .map(String::from);
+        assert_eq!(expand("./crates/*/", &dirs), ["crates/core", "crates/ui"]);
+        assert_eq!(expand("web", &dirs), ["web"]);
+        assert!(expand("apps/*", &dirs).is_empty());
+    }
+
+    #[test]
+    fn names_each_path_once() {
+        let found = unique(vec![candidate("crates/core"), candidate("crates/core")]);
+        assert_eq!(scopes(&found), ["core"]);
+        let found = unique(vec![
+            candidate("crates/core"),
+            candidate("web/core"),
+            candidate("src/cli"),
+        ]);
+        assert_eq!(scopes(&found), ["crates-core", "web-core", "cli"]);
+        let found = unique(vec![candidate("a/lib/core"), candidate("b/lib/core")]);
+        assert_eq!(scopes(&found), ["a-lib-core", "b-lib-core"]);
+    }
+}
@@ -1,70 +1,103 @@
-use crate::config::load_config;
-use crate::utils::ok;
-use regex::Regex;
-use std::io::Error;
-use std::process::{Command, ExitCode};
+use crate::config::{Documentation, load_config, try_load_config};
+use crate::pipeline::{Entry, Status, Task, run, summary, trust};
+use clap_mangen::Man;
+use crossterm::style::Stylize;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Instant;
 
-/// check if a command it's safe to execute
-/// # Panics
-/// if the regex is not valid
-#[must_use]
-pub fn is_safe_command(cmd: &str) -> bool {
-    let re = Regex::new(r"([;&|`$]|\.\.)").expect("invalid regex for is_safe_command");
-    let starts_with_slash = cmd.trim().starts_with('/');
-    let allowed_bins = ["cargo", "doxygen", "rm", "cp", "mkdir", "touch"];
-    let binary_name = cmd.split_whitespace().next().unwrap_or("");
-    let is_allowed_bin = allowed_bins.contains(&binary_name);
-    !re.is_match(cmd) && !starts_with_slash && is_allowed_bin
+fn run_steps(steps: &[Entry], documentation: &Documentation, force: bool) -> ExitCode {
+    if steps.is_empty() {
+        return ExitCode::SUCCESS;
+    }
+    let tasks = steps.iter().map(Entry::task).collect::<Vec<Task>>();
+    for task in &tasks {
+        if let Err(e) = task.check(&documentation.allow) {
+            eprintln!("{} {}", "!".red(), e.to_string().yellow());
+            return ExitCode::FAILURE;
+        }
+    }
+    match trust(documentation) {
+        Ok(true) => {}
+        Ok(false) => return ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("failed to check the trust of the steps: {e}");
+            return ExitCode::FAILURE;
+        }
+    }
+    let start = Instant::now();
+    match run(&tasks, force) {
+        Ok(reports) => {
+            summary(&reports, start.elapsed());
+            if reports
+                .iter()
+                .all(|report| matches!(report.status, Status::Ran | Status::Cached))
+            {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Err(e) => {
+            eprintln!("{} {}", "!".red(), e.to_string().yellow());
+            ExitCode::FAILURE
+        }
+    }
 }
 
-/// run a command and describe it with success and failure messages
-///
-/// # Errors
-/// on no founded command or bad user input
-pub fn describe(it: &str, command: &str, success: &str, failure: &str) -> Result<(), Error> {
-    if !is_safe_command(command) {
-        return Err(Error::other("Security check failed"));
-    }
-    if cfg!(windows) {
-        ok(
-            it,
-            Command::new("cmd").arg("/C").arg(command),
-            success,
-            failure,
-        )
-    } else {
-        ok(
-            it,
-            Command::new("sh").arg("-c").arg(command),
-            success,
-            failure,
-        )
+fn write_pages(mut cmd: clap::Command, dir: &Path) -> Result<usize, Error> {
+    cmd.build();
+    std::fs::create_dir_all(dir)?;
+    let mut pending = vec![cmd];
+    let mut count = 0;
+    while let Some(cmd) = pending.pop() {
+        let name = cmd
+            .get_display_name()
+            .unwrap_or_else(|| cmd.get_name())
+            .to_string();
+        let mut page = Vec::new();
+        Man::new(cmd.clone()).render(&mut page)?;
+        std::fs::write(dir.join(format!("{name}.1")), page)?;
+        count += 1;
+        pending.extend(
+            cmd.get_subcommands()
+                .filter(|sub| sub.get_name() != "help")
+                .cloned(),
+        );
     }
+    Ok(count)
 }
 
 ///
-/// Generate man pages
+/// Generate the man pages of a command and its subcommands, then run the man steps on them
 ///
 /// # Errors
 /// On bad user inputs
 ///
 #[must_use]
-pub fn generate_man() -> ExitCode {
-    let config = load_config();
-    let len = config.documentation.man.len();
-    for (i, command) in config.documentation.man.iter().enumerate() {
-        if describe(
-            format!("Building Man pages {}/{len}", i + 1).as_str(),
-            command.as_str(),
-            format!("Man step {}/{len} has been completed successfully", i + 1).as_str(),
-            format!("Man step {}/{len} has failed", i + 1).as_str(),
-        )
-        .is_err()
-        {
+pub fn generate_man(cmd: clap::Command, output: Option<&str>, force: bool) -> ExitCode {
+    let documentation = match try_load_config() {
+        Ok(config) => config.documentation,
+        Err(e) if e.kind() == ErrorKind::NotFound => Documentation::default(),
+        Err(e) => {
+            eprintln!("failed to read the configuration: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let dir = output.map_or_else(|| documentation.man_dir.clone(), String::from);
+    match write_pages(cmd, Path::new(dir.as_str())) {
+        Ok(count) => println!(
+            "{} {}",
+            "✓".green(),
+            format!("{count} man pages written to {dir}").dark_cyan()
+        ),
+        Err(e) => {
+            eprintln!("failed to write the man pages: {e}");
             return ExitCode::FAILURE;
         }
     }
-    ExitCode::SUCCESS
+    run_steps(&documentation.man, &documentation, force)
 }
 ///
 /// generate github pages docs
@@ -72,24 +105,7 @@ pub fn generate_man() -> ExitCode {
 /// # Errors
 /// On bad user inputs
 #[must_use]
-pub fn generate_doc() -> ExitCode {
-    let config = load_config();
-    let len = config.documentation.doc.len();
-    for (i, command) in config.documentation.doc.iter().enumerate() {
-        if describe(
-            format!("Building documentation {}/{len}", i + 1).as_str(),
-            command.as_str(),
-            format!(
-                "Documentation step {}/{len} has been completed successfully",
-                i + 1
-            )
-            .as_str(),
-            format!("Documentation step {}/{len} has failed", i + 1).as_str(),
-        )
-        .is_err()
-        {
-            return ExitCode::FAILURE;
-        }
-    }
-    ExitCode::SUCCESS
+pub fn generate_doc(force: bool) -> ExitCode {
+    let documentation = load_config().documentation;
+    run_steps(&documentation.doc, &documentation, force)
 }
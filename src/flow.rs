@@ -0,0 +1,166 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+#[derive(
+    Deserialize, Serialize, JsonSchema, Ord, PartialOrd, Eq, PartialEq, Debug, Clone, Copy, Hash,
+)]
+#[serde(rename_all = "snake_case")]
+#[doc = "A question asked by the commit wizard"]
+pub enum Step {
+    Revision,
+    Version,
+    Scopes,
+    Summary,
+    Roles,
+    Why,
+    BreakingChanges,
+    What,
+    Who,
+    CoAuthors,
+    Benefits,
+    Notes,
+    Resolves,
+}
+
+impl Step {
+    ///
+    /// The default order of the wizard questions, asked after the commit type
+    ///
+    #[must_use]
+    pub fn all() -> Vec<Self> {
+        vec![
+            Self::Scopes,
+            Self::Summary,
+            Self::Roles,
+            Self::Why,
+            Self::BreakingChanges,
+            Self::What,
+            Self::Who,
+            Self::CoAuthors,
+            Self::Benefits,
+            Self::Notes,
+            Self::Resolves,
+        ]
+    }
+
+    ///
+    /// Steps that loop until an answer is given unless a flow marks them optional
+    ///
+    #[must_use]
+    pub const fn required_by_default(self) -> bool {
+        !matches!(
+            self,
+            Self::BreakingChanges | Self::CoAuthors | Self::Notes | Self::Resolves
+        )
+    }
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Revision => write!(f, "revision"),
+            Self::Version => write!(f, "version"),
+            Self::Scopes => write!(f, "scopes"),
+            Self::Summary => write!(f, "summary"),
+            Self::Roles => write!(f, "roles"),
+            Self::Why => write!(f, "why"),
+            Self::BreakingChanges => write!(f, "breaking_changes"),
+            Self::What => write!(f, "what"),
+            Self::Who => write!(f, "who"),
+            Self::CoAuthors => write!(f, "co_authors"),
+            Self::Benefits => write!(f, "benefits"),
+            Self::Notes => write!(f, "notes"),
+            Self::Resolves => write!(f, "resolves"),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Default, Debug, Clone, PartialEq, Eq)]
+#[doc = "The questions asked for a commit type"]
+pub struct Flow {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<Step>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skip: Vec<Step>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub require: Vec<Step>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub optional: Vec<Step>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub when: BTreeMap<Step, Step>,
+}
+
+impl Flow {
+    ///
+    /// The flow used for a commit type without one in breath.yml
+    ///
+    #[must_use]
+    pub fn builtin(t: &str) -> Self {
+        match t {
+            "revert" => Self {
+                steps: [vec![Step::Revision], Step::all()].concat(),
+                skip: vec![Step::Benefits],
+                ..Self::default()
+            },
+            "release" | "bump" => Self {
+                steps: [vec![Step::Version], Step::all()].concat(),
+                ..Self::default()
+            },
+            "fix" => Self {
+                require: vec![Step::Resolves],
+                ..Self::default()
+            },
+            "docs" => Self {
+                skip: vec![Step::Roles, Step::Benefits],
+                ..Self::default()
+            },
+            _ => Self::default(),
+        }
+    }
+
+    ///
+    /// The built-in flows written by `breath init`
+    ///
+    #[must_use]
+    pub fn defaults() -> BTreeMap<String, Self> {
+        ["revert", "release", "bump", "fix", "docs"]
+            .into_iter()
+            .map(|t| (t.to_string(), Self::builtin(t)))
+            .collect()
+    }
+
+    ///
+    /// The ordered steps to ask, without the skipped ones
+    ///
+    #[must_use]
+    pub fn steps(&self) -> Vec<Step> {
+        let steps = if self.steps.is_empty() {
+            Step::all()
+        } else {
+            self.steps.clone()
+        };
+        steps
+            .into_iter()
+            .filter(|step| !self.skip.contains(step))
+            .collect()
+    }
+
+    ///
+    /// Check if a step must be answered
+    ///
+    #[must_use]
+    pub fn is_required(&self, step: Step) -> bool {
+        self.require.contains(&step)
+            || (step.required_by_default() && !self.optional.contains(&step))
+    }
+
+    ///
+    /// The step which must have an answer before asking `step`
+    ///
+    #[must_use]
+    pub fn condition(&self, step: Step) -> Option<Step> {
+        self.when.get(&step).copied()
+    }
+}
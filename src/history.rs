@@ -0,0 +1,317 @@
+use crate::commit::{Commit, history, vcs};
+use crate::config::try_load_config;
+use crossterm::style::{Color, Stylize};
+use serde::Serialize;
+use std::io::Error;
+use std::str::FromStr;
+
+///
+/// The formats `breath log` can print
+///
+pub const FORMATS: [&str; 3] = ["table", "json", "csv"];
+
+const SUMMARY_WIDTH: usize = 60;
+
+const COLORS: [Color; 6] = [
+    Color::Green,
+    Color::Cyan,
+    Color::Magenta,
+    Color::Blue,
+    Color::DarkYellow,
+    Color::DarkCyan,
+];
+
+///
+/// The filters of `breath log`, a commit must match all of them
+///
+#[derive(Default, Debug, Clone)]
+pub struct Filter {
+    pub types: Vec<String>,
+    pub scopes: Vec<String>,
+    pub roles: Vec<String>,
+    pub breaking: bool,
+    pub resolves: Option<String>,
+    pub since: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl Filter {
+    ///
+    /// Whether a commit passes the filter
+    ///
+    #[must_use]
+    pub fn matches(&self, commit: &Commit) -> bool {
+        (self.types.is_empty() || self.types.contains(&commit.t))
+            && (self.scopes.is_empty() || commit.scopes.iter().any(|s| self.scopes.contains(s)))
+            && (self.roles.is_empty() || commit.roles.iter().any(|r| self.roles.contains(r)))
+            && (!self.breaking || is_breaking(commit))
+            && self.resolves.as_ref().is_none_or(|issue| {
+                commit
+                    .resolves
+                    .iter()
+                    .any(|id| id.trim_start_matches('#') == issue.trim_start_matches('#'))
+            })
+    }
+}
+
+fn is_breaking(commit: &Commit) -> bool {
+    !commit.breaking_changes.trim().is_empty()
+}
+
+///
+/// A breath commit of the history, as printed by `breath log`
+///
+#[derive(Serialize, Debug, Clone)]
+pub struct Row {
+    pub id: String,
+    pub date: String,
+    pub author: String,
+    #[serde(rename = "type")]
+    pub t: String,
+    pub scopes: Vec<String>,
+    pub summary: String,
+    pub roles: Vec<String>,
+    pub resolves: Vec<String>,
+    pub breaking: bool,
+}
+
+///
+/// The breath commits of a revset matching a filter, newest first
+///
+/// Revisions whose message is not a breath message are left out.
+///
+#[must_use]
+pub fn rows(revset: &str, filter: &Filter) -> Vec<Row> {
+    history(revset, filter.since.as_deref())
+        .into_iter()
+        .filter_map(|revision| {
+            let commit = Commit::from_str(revision.message.trim()).ok()?;
+            filter.matches(&commit).then(|| Row {
+                id: revision.id,
+                date: revision.date,
+                author: revision.author,
+                breaking: is_breaking(&commit),
+                t: commit.t,
+                scopes: commit.scopes,
+                summary: commit.summary,
+                roles: commit.roles,
+                resolves: commit.resolves,
+            })
+        })
+        .take(filter.limit.unwrap_or(usize::MAX))
+        .collect()
+}
+
+fn shorten(text: &str, width: usize) -> String {
+    if text.chars().count() > width {
+        let mut short = text.chars().take(width - 1).collect::<String>();
+        short.push('…');
+        short
+    } else {
+        text.to_string()
+    }
+}
+
+fn table(rows: &[Row]) {
+    let types = try_load_config()
+        .map(|config| {
+            config
+                .breathes
+                .types
+                .iter()
+                .map(|t| t.split('~').next().unwrap_or_default().trim().to_string())
+                .collect::<Vec<String>>()
+        })
+        .unwrap_or_default();
+    let cells = rows
+        .iter()
+        .map(|row| {
+            [
+                row.id.get(..8).unwrap_or(&row.id).to_string(),
+                row.date.clone(),
+                row.t.clone(),
+                row.scopes.join(" "),
+                shorten(row.summary.as_str(), SUMMARY_WIDTH),
+                row.author.clone(),
+                row.resolves
+                    .iter()
+                    .map(|id| format!("#{}", id.trim_start_matches('#')))
+                    .collect::<Vec<String>>()
+                    .join(" "),
+            ]
+        })
+        .collect::<Vec<[String; 7]>>();
+    let header = [
+        "rev", "date", "type", "scopes", "summary", "author", "resolves",
+    ];
+    let mut widths = header.map(str::len);
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: &[String]| {
+        cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<String>>()
+    };
+    println!(
+        "  {}",
+        line(&header.map(String::from)).join("  ").trim_end().bold()
+    );
+    for (row, cells) in rows.iter().zip(&cells) {
+        let mut columns = line(cells);
+        let color = types
+            .iter()
+            .position(|t| t.eq(&row.t))
+            .map_or(Color::Yellow, |index| COLORS[index % COLORS.len()]);
+        columns[0] = columns[0].as_str().dark_grey().to_string();
+        columns[2] = columns[2].as_str().with(color).to_string();
+        let marker = if row.breaking {
+            "!".red().bold().to_string()
+        } else {
+            String::from(" ")
+        };
+        println!("{marker} {}", columns.join("  ").trim_end());
+    }
+}
+
+///
+/// A CSV field, quoted when it holds a comma, a quote or a line break
+///
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv(rows: &[Row]) {
+    println!("id,date,author,type,scopes,summary,roles,resolves,breaking");
+    for row in rows {
+        println!(
+            "{}",
+            [
+                field(row.id.as_str()),
+                field(row.date.as_str()),
+                field(row.author.as_str()),
+                field(row.t.as_str()),
+                field(row.scopes.join(" ").as_str()),
+                field(row.summary.as_str()),
+                field(row.roles.join(" ").as_str()),
+                field(row.resolves.join(" ").as_str()),
+                row.breaking.to_string(),
+            ]
+            .join(",")
+        );
+    }
+}
+
+///
+/// Print the breath commits of a revset, the history of the working directory by default
+///
+/// Mercurial revsets are reversed to list the newest commits first, as git does.
+///
+/// # Errors
+/// If the rows cannot be written as JSON
+pub fn log(revset: Option<&str>, filter: &Filter, format: &str) -> Result<(), Error> {
+    let revset = if vcs().eq("hg") {
+        format!("reverse({})", revset.unwrap_or("::."))
+    } else {
+        String::from(revset.unwrap_or("HEAD"))
+    };
+    let rows = rows(revset.as_str(), filter);
+    match format {
+        "json" => println!(
+            "{}",
+            serde_json::to_string_pretty(&rows).map_err(Error::other)?
+        ),
+        "csv" => csv(&rows),
+        _ => table(&rows),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Filter, field};
+    use crate::commit::Commit;
+
+    fn commit() -> Commit {
+        Commit {
+            t: String::from("fix"),
+            scopes: vec![String::from("core"), String::from("ui")],
+            roles: vec![String::from("Developer")],
+            resolves: vec![String::from("#42"), String::from("BR-7")],
+            ..Commit::default()
+        }
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn matches_every_filter() {
+        let commit = commit();
+        assert!(Filter::default().matches(&commit));
+        let filter = Filter {
+            types: strings(&["feat", "fix"]),
+            scopes: strings(&["ui"]),
+            roles: strings(&["Developer"]),
+            resolves: Some(String::from("42")),
+            ..Filter::default()
+        };
+        assert!(filter.matches(&commit));
+        assert!(
+            Filter {
+                resolves: Some(String::from("BR-7")),
+                ..Filter::default()
+            }
+            .matches(&commit)
+        );
+        let rejects = |filter: Filter| !filter.matches(&commit);
+        assert!(rejects(Filter {
+            types: strings(&["feat"]),
+            ..Filter::default()
+        }));
+        assert!(rejects(Filter {
+            scopes: strings(&["docs"]),
+            ..Filter::default()
+        }));
+        assert!(rejects(Filter {
+            roles: strings(&["Tester"]),
+            ..Filter::default()
+        }));
+        assert!(rejects(Filter {
+            resolves: Some(String::from("#4")),
+            ..Filter::default()
+        }));
+        assert!(rejects(Filter {
+            breaking: true,
+            ..Filter::default()
+        }));
+        let breaking = Commit {
+            breaking_changes: String::from("the cache moved"),
+            ..commit.clone()
+        };
+        assert!(
+            Filter {
+                breaking: true,
+                ..Filter::default()
+            }
+            .matches(&breaking)
+        );
+    }
+
+    #[test]
+    fn quotes_the_csv_fields() {
+        assert_eq!(field("plain summary"), "plain summary");
+        assert_eq!(field("core,ui"), "\"core,ui\"");
+        assert_eq!(field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(field("two\nlines"), "\"two\nlines\"");
+    }
+}
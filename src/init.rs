@@ -0,0 +1,182 @@
+use crate::commit::{Style, vcs};
+use crate::config::{BreathConfig, builtin_roles, config_source, default_required, write_config};
+use crate::discover::discover;
+use crate::flow::Step;
+use crate::pipeline::Entry;
+use crate::utils::ok;
+use crossterm::style::Stylize;
+use inquire::{MultiSelect, Select};
+use std::fmt::{Display, Formatter};
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use std::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[doc = "The main language of the project"]
+pub enum Language {
+    Rust,
+    Cpp,
+    Node,
+    Go,
+    Python,
+    Unknown,
+}
+
+impl Language {
+    ///
+    /// Detect the language from the manifests at the root of the repository
+    ///
+    #[must_use]
+    pub fn detect() -> Self {
+        let found = |files: &[&str]| files.iter().any(|file| Path::new(file).is_file());
+        if found(&["Cargo.toml"]) {
+            Self::Rust
+        } else if found(&["CMakeLists.txt", "meson.build", "Doxyfile"]) {
+            Self::Cpp
+        } else if found(&["package.json"]) {
+            Self::Node
+        } else if found(&["go.mod"]) {
+            Self::Go
+        } else if found(&["pyproject.toml", "setup.py"]) {
+            Self::Python
+        } else {
+            Self::Unknown
+        }
+    }
+
+    ///
+    /// The documentation steps suited to the language
+    ///
+    #[must_use]
+    pub fn doc(self) -> Vec<Entry> {
+        let writing = |line: &str, output: &str| {
+            let mut task = Entry::Line(String::from(line)).task();
+            task.outputs.push(String::from(output));
+            Entry::Task(task)
+        };
+        match self {
+            Self::Rust => vec![
+                Entry::Line(String::from("cargo doc --no-deps")),
+                writing("cp -r target/doc docs", "docs"),
+                writing("touch docs/.nojekyll", "docs"),
+            ],
+            Self::Cpp => vec![Entry::Line(String::from("doxygen"))],
+            Self::Python => vec![writing("sphinx-build docs docs/_build", "docs/_build")],
+            Self::Node | Self::Go | Self::Unknown => vec![],
+        }
+    }
+}
+
+impl Display for Language {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rust => write!(f, "Rust"),
+            Self::Cpp => write!(f, "C/C++"),
+            Self::Node => write!(f, "Node"),
+            Self::Go => write!(f, "Go"),
+            Self::Python => write!(f, "Python"),
+            Self::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+fn init_vcs(defaults: bool, create: Option<&str>) -> Result<String, Error> {
+    if Path::new(".git").is_dir() || Path::new(".hg").is_dir() {
+        return Ok(vcs());
+    }
+    let vcs = if let Some(create) = create {
+        create
+    } else if defaults {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            "no repository found, use --vcs git or --vcs hg to initialize one",
+        ));
+    } else {
+        Select::new(
+            "No repository found, initialize one with",
+            vec!["git", "hg"],
+        )
+        .prompt()
+        .map_err(Error::other)?
+    };
+    ok(
+        "Initializing the repository",
+        Command::new(vcs).arg("init"),
+        "Repository initialized",
+        "Failed to initialize the repository",
+    )?;
+    Ok(vcs.to_string())
+}
+
+///
+/// Write a breath.yml suited to the repository, asking for each choice unless `defaults` is set
+///
+/// Without a repository, one of `create` is initialized, asked for unless `defaults` is set.
+///
+/// # Errors
+/// If breath.yml already exists without `force`, on bad user inputs or if breath.yml cannot be written
+pub fn init(force: bool, defaults: bool, create: Option<&str>) -> Result<(), Error> {
+    if let Some(source) = config_source()?
+        && !force
+    {
+        return Err(Error::new(
+            ErrorKind::AlreadyExists,
+            format!("{source} already exists, use --force to overwrite it"),
+        ));
+    }
+    let vcs = init_vcs(defaults, create)?;
+    let language = Language::detect();
+    println!(
+        "{} {}",
+        "✓".green(),
+        format!("{vcs} repository, {language} project").dark_cyan()
+    );
+    let mut config = BreathConfig::default();
+    let mut candidates = discover();
+    if !defaults && !candidates.is_empty() {
+        candidates = MultiSelect::new("Scopes found in the repository", candidates)
+            .with_all_selected_by_default()
+            .prompt()
+            .map_err(Error::other)?;
+    }
+    for candidate in candidates {
+        config.breathes.paths.insert(
+            candidate.scope.clone(),
+            vec![format!("{}/**", candidate.path)],
+        );
+        config.breathes.scopes.push(candidate.scope);
+    }
+    config.documentation.doc = language.doc();
+    if defaults {
+        return write_config(&config, force);
+    }
+    config.breathes.style = Select::new("Commit message style", Style::all())
+        .prompt()
+        .map_err(Error::other)?;
+    config.breathes.roles = MultiSelect::new("Roles of the team", builtin_roles())
+        .with_all_selected_by_default()
+        .prompt()
+        .map_err(Error::other)?;
+    let required = default_required();
+    let steps = Step::all();
+    let selected = steps
+        .iter()
+        .enumerate()
+        .filter(|(_, step)| required.contains(step))
+        .map(|(i, _)| i)
+        .collect::<Vec<usize>>();
+    config.breathes.required = MultiSelect::new("Required sections", steps)
+        .with_default(&selected)
+        .prompt()
+        .map_err(Error::other)?;
+    if !config.documentation.doc.is_empty() {
+        config.documentation.doc = MultiSelect::new(
+            format!("Documentation steps for {language}").as_str(),
+            config.documentation.doc,
+        )
+        .with_all_selected_by_default()
+        .prompt()
+        .map_err(Error::other)?;
+    }
+    write_config(&config, force)
+}
@@ -0,0 +1,636 @@
+use crate::config::Issues;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use std::io::Error;
+use std::time::Duration;
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[doc = "The issue trackers supported by breath"]
+pub enum Provider {
+    GitHub,
+    GitLab,
+    Gitea,
+    Jira,
+    Local,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[doc = "An issue of the tracker"]
+pub struct Issue {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub closed: bool,
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ~ {}", self.id, self.title)
+    }
+}
+
+#[doc = "A source of issues resolved by commits"]
+pub trait IssueProvider {
+    ///
+    /// List the open issues
+    ///
+    /// # Errors
+    ///
+    /// If the tracker cannot be reached or answers with bad data
+    ///
+    fn open_issues(&self) -> Result<Vec<Issue>, Error>;
+
+    ///
+    /// Find an issue by id, `None` if it does not exist
+    ///
+    /// # Errors
+    ///
+    /// If the tracker cannot be reached or answers with bad data
+    ///
+    fn issue(&self, id: &str) -> Result<Option<Issue>, Error>;
+
+    ///
+    /// The closing keyword of the tracker, `{id}` is replaced by the issue id
+    ///
+    fn keyword(&self) -> &'static str {
+        "Fixes #{id}"
+    }
+}
+
+///
+/// Build the issue provider configured in breath.yml
+///
+#[must_use]
+pub fn provider(issues: &Issues) -> Box<dyn IssueProvider + Send + Sync> {
+    let token = issues
+        .token
+        .as_deref()
+        .and_then(|name| std::env::var(name).ok());
+    match issues.provider {
+        Provider::GitHub => Box::new(GitHub {
+            url: issues
+                .url
+                .clone()
+                .unwrap_or_else(|| String::from("https://api.github.com")),
+            project: issues.project.clone(),
+            token,
+        }),
+        Provider::GitLab => Box::new(GitLab {
+            url: issues
+                .url
+                .clone()
+                .unwrap_or_else(|| String::from("https://gitlab.com")),
+            project: issues.project.clone(),
+            token,
+        }),
+        Provider::Gitea => Box::new(Gitea {
+            url: issues
+                .url
+                .clone()
+                .unwrap_or_else(|| String::from("https://gitea.com")),
+            project: issues.project.clone(),
+            token,
+        }),
+        Provider::Jira => Box::new(Jira {
+            url: issues.url.clone().unwrap_or_default(),
+            project: issues.project.clone(),
+            token,
+        }),
+        Provider::Local => Box::new(Local {
+            file: issues
+                .file
+                .clone()
+                .unwrap_or_else(|| String::from("issues.yml")),
+        }),
+    }
+}
+
+///
+/// The most pages fetched when listing the open issues
+///
+const MAX_PAGES: usize = 10;
+
+fn request(
+    url: &str,
+    auth: Option<&(&str, String)>,
+    query: &[(String, String)],
+) -> Result<(Value, ureq::http::HeaderMap), Error> {
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(Duration::from_secs(10)))
+        .build()
+        .into();
+    let mut request = agent.get(url).header("Accept", "application/json");
+    if let Some((header, value)) = auth {
+        request = request.header(*header, value.as_str());
+    }
+    for (key, value) in query {
+        request = request.query(key, value);
+    }
+    let mut response = request.call().map_err(Error::other)?;
+    let value = response
+        .body_mut()
+        .read_json::<Value>()
+        .map_err(Error::other)?;
+    Ok((value, response.headers().clone()))
+}
+
+fn get(url: &str, auth: Option<(&str, String)>, query: &[(&str, &str)]) -> Result<Value, Error> {
+    let query = query
+        .iter()
+        .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+        .collect::<Vec<(String, String)>>();
+    request(url, auth.as_ref(), &query).map(|(value, _)| value)
+}
+
+fn header<'a>(headers: &'a ureq::http::HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+///
+/// The `rel="next"` target of a `Link` header
+///
+fn next_link(link: &str) -> Option<String> {
+    link.split(',')
+        .find(|part| part.contains("rel=\"next\""))
+        .and_then(|part| {
+            let start = part.find('<')?;
+            let end = part.find('>')?;
+            part.get(start + 1..end).map(String::from)
+        })
+}
+
+///
+/// Fetch every page of a list, following the `Link` (GitHub, Gitea) or `X-Next-Page` (GitLab) headers
+///
+fn pages(
+    url: &str,
+    auth: Option<(&str, String)>,
+    query: &[(&str, &str)],
+) -> Result<Vec<Value>, Error> {
+    let mut items = Vec::new();
+    let mut next = Some((
+        url.to_string(),
+        query
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect::<Vec<(String, String)>>(),
+    ));
+    for _ in 0..MAX_PAGES {
+        let Some((url, mut query)) = next.take() else {
+            break;
+        };
+        let (value, headers) = request(url.as_str(), auth.as_ref(), &query)?;
+        items.extend(value.as_array().cloned().unwrap_or_default());
+        if let Some(link) = header(&headers, "link").and_then(next_link) {
+            next = Some((link, Vec::new()));
+        } else if let Some(page) = header(&headers, "x-next-page") {
+            query.retain(|(key, _)| key != "page");
+            query.push((String::from("page"), page.to_string()));
+            next = Some((url, query));
+        }
+    }
+    Ok(items)
+}
+
+fn find(url: &str, auth: Option<(&str, String)>) -> Result<Option<Value>, Error> {
+    match get(url, auth, &[]) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) => match e.get_ref().and_then(|e| e.downcast_ref::<ureq::Error>()) {
+            Some(ureq::Error::StatusCode(404)) => Ok(None),
+            _ => Err(e),
+        },
+    }
+}
+
+fn text(value: &Value, key: &str) -> String {
+    match &value[key] {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+#[doc = "Issues of a GitHub repository"]
+pub struct GitHub {
+    pub url: String,
+    pub project: String,
+    pub token: Option<String>,
+}
+
+impl GitHub {
+    fn auth(&self) -> Option<(&str, String)> {
+        self.token
+            .as_ref()
+            .map(|token| ("Authorization", format!("Bearer {token}")))
+    }
+}
+
+impl IssueProvider for GitHub {
+    fn open_issues(&self) -> Result<Vec<Issue>, Error> {
+        let url = format!("{}/repos/{}/issues", self.url, self.project);
+        let issues = pages(
+            url.as_str(),
+            self.auth(),
+            &[("state", "open"), ("per_page", "100")],
+        )?;
+        Ok(issues
+            .iter()
+            .filter(|issue| issue["pull_request"].is_null())
+            .map(|issue| Issue {
+                id: text(issue, "number"),
+                title: text(issue, "title"),
+                closed: false,
+            })
+            .collect())
+    }
+
+    fn issue(&self, id: &str) -> Result<Option<Issue>, Error> {
+        let url = format!("{}/repos/{}/issues/{id}", self.url, self.project);
+        Ok(find(url.as_str(), self.auth())?.map(|issue| Issue {
+            id: text(&issue, "number"),
+            title: text(&issue, "title"),
+            closed: text(&issue, "state") == "closed",
+        }))
+    }
+}
+
+#[doc = "Issues of a GitLab project"]
+pub struct GitLab {
+    pub url: String,
+    pub project: String,
+    pub token: Option<String>,
+}
+
+impl GitLab {
+    fn auth(&self) -> Option<(&str, String)> {
+        self.token
+            .as_ref()
+            .map(|token| ("PRIVATE-TOKEN", token.clone()))
+    }
+
+    fn project_url(&self) -> String {
+        format!(
+            "{}/api/v4/projects/{}",
+            self.url,
+            self.project.replace('/', "%2F")
+        )
+    }
+}
+
+impl IssueProvider for GitLab {
+    fn open_issues(&self) -> Result<Vec<Issue>, Error> {
+        let url = format!("{}/issues", self.project_url());
+        let issues = pages(
+            url.as_str(),
+            self.auth(),
+            &[("state", "opened"), ("per_page", "100")],
+        )?;
+        Ok(issues
+            .iter()
+            .map(|issue| Issue {
+                id: text(issue, "iid"),
+                title: text(issue, "title"),
+                closed: false,
+            })
+            .collect())
+    }
+
+    fn issue(&self, id: &str) -> Result<Option<Issue>, Error> {
+        let url = format!("{}/issues/{id}", self.project_url());
+        Ok(find(url.as_str(), self.auth())?.map(|issue| Issue {
+            id: text(&issue, "iid"),
+            title: text(&issue, "title"),
+            closed: text(&issue, "state") == "closed",
+        }))
+    }
+
+    fn keyword(&self) -> &'static str {
+        "Closes #{id}"
+    }
+}
+
+#[doc = "Issues of a Gitea or Forgejo repository"]
+pub struct Gitea {
+    pub url: String,
+    pub project: String,
+    pub token: Option<String>,
+}
+
+impl Gitea {
+    fn auth(&self) -> Option<(&str, String)> {
+        self.token
+            .as_ref()
+            .map(|token| ("Authorization", format!("token {token}")))
+    }
+}
+
+impl IssueProvider for Gitea {
+    fn open_issues(&self) -> Result<Vec<Issue>, Error> {
+        let url = format!("{}/api/v1/repos/{}/issues", self.url, self.project);
+        let issues = pages(
+            url.as_str(),
+            self.auth(),
+            &[("state", "open"), ("type", "issues"), ("limit", "50")],
+        )?;
+        Ok(issues
+            .iter()
+            .map(|issue| Issue {
+                id: text(issue, "number"),
+                title: text(issue, "title"),
+                closed: false,
+            })
+            .collect())
+    }
+
+    fn issue(&self, id: &str) -> Result<Option<Issue>, Error> {
+        let url = format!("{}/api/v1/repos/{}/issues/{id}", self.url, self.project);
+        Ok(find(url.as_str(), self.auth())?.map(|issue| Issue {
+            id: text(&issue, "number"),
+            title: text(&issue, "title"),
+            closed: text(&issue, "state") == "closed",
+        }))
+    }
+
+    fn keyword(&self) -> &'static str {
+        "Closes #{id}"
+    }
+}
+
+#[doc = "Issues of a Jira project"]
+pub struct Jira {
+    pub url: String,
+    pub project: String,
+    pub token: Option<String>,
+}
+
+impl Jira {
+    fn auth(&self) -> Option<(&str, String)> {
+        self.token
+            .as_ref()
+            .map(|token| ("Authorization", format!("Bearer {token}")))
+    }
+
+    fn issue_of(value: &Value) -> Issue {
+        Issue {
+            id: text(value, "key"),
+            title: text(&value["fields"], "summary"),
+            closed: text(&value["fields"]["status"]["statusCategory"], "key") == "done",
+        }
+    }
+}
+
+impl IssueProvider for Jira {
+    fn open_issues(&self) -> Result<Vec<Issue>, Error> {
+        let url = format!("{}/rest/api/3/search/jql", self.url);
+        let jql = format!(
+            "project = \"{}\" AND statusCategory != Done ORDER BY updated DESC",
+            self.project
+        );
+        let mut issues = Vec::new();
+        let mut token = String::new();
+        for _ in 0..MAX_PAGES {
+            let mut query = vec![
+                ("jql", jql.as_str()),
+                ("fields", "summary,status"),
+                ("maxResults", "100"),
+            ];
+            if !token.is_empty() {
+                query.push(("nextPageToken", token.as_str()));
+            }
+            let result = get(url.as_str(), self.auth(), &query)?;
+            let page = result["issues"].as_array().cloned().unwrap_or_default();
+            issues.extend(page.iter().map(Self::issue_of));
+            token = text(&result, "nextPageToken");
+            if page.is_empty() || token.is_empty() || result["isLast"].as_bool() == Some(true) {
+                break;
+            }
+        }
+        Ok(issues)
+    }
+
+    fn issue(&self, id: &str) -> Result<Option<Issue>, Error> {
+        let url = format!("{}/rest/api/3/issue/{id}", self.url);
+        Ok(find(url.as_str(), self.auth())?.map(|issue| Self::issue_of(&issue)))
+    }
+
+    fn keyword(&self) -> &'static str {
+        "{id} #close"
+    }
+}
+
+#[doc = "Issues listed in a local yaml file, for offline use"]
+pub struct Local {
+    pub file: String,
+}
+
+impl Local {
+    fn issues(&self) -> Result<Vec<Issue>, Error> {
+        let content = std::fs::read_to_string(self.file.as_str()).map_err(|e| {
+            Error::new(
+                e.kind(),
+                format!("cannot read the issues file {}: {e}", self.file),
+            )
+        })?;
+        serde_yaml::from_str(&content)
+            .map_err(|e| Error::other(format!("bad issues file {}: {e}", self.file)))
+    }
+}
+
+impl IssueProvider for Local {
+    fn open_issues(&self) -> Result<Vec<Issue>, Error> {
+        Ok(self
+            .issues()?
+            .into_iter()
+            .filter(|issue| !issue.closed)
+            .collect())
+    }
+
+    fn issue(&self, id: &str) -> Result<Option<Issue>, Error> {
+        Ok(self.issues()?.into_iter().find(|issue| issue.id == id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GitHub, GitLab, Issue, IssueProvider, Jira, Local, next_link};
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    ///
+    /// A status, the headers where `{url}` is the server url, and a body
+    ///
+    type Reply<'a> = (&'a str, Vec<(&'a str, String)>, &'a str);
+
+    ///
+    /// Answer each request with the next reply, returning the url to call and the received
+    /// request heads
+    ///
+    fn serve(responses: Vec<Reply<'_>>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let url = format!("http://{}", listener.local_addr().expect("address"));
+        let responses = responses
+            .into_iter()
+            .map(|(status, headers, body)| {
+                let mut response = format!(
+                    "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+                    body.len()
+                );
+                for (name, value) in headers {
+                    let value = value.replace("{url}", url.as_str());
+                    response.push_str(format!("{name}: {value}\r\n").as_str());
+                }
+                response.push_str("\r\n");
+                response.push_str(body);
+                response
+            })
+            .collect::<Vec<String>>();
+        let server = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().expect("accept");
+                let mut reader = BufReader::new(stream.try_clone().expect("clone"));
+                let mut head = String::new();
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).expect("read") == 0 || line.trim().is_empty() {
+                        break;
+                    }
+                    head.push_str(line.to_lowercase().as_str());
+                }
+                requests.push(head);
+                stream.write_all(response.as_bytes()).expect("write");
+            }
+            requests
+        });
+        (url, server)
+    }
+
+    fn issue(id: &str, title: &str) -> Issue {
+        Issue {
+            id: id.to_string(),
+            title: title.to_string(),
+            closed: false,
+        }
+    }
+
+    #[test]
+    fn github_follows_the_link_header() {
+        let link =
+            "<https://x/issues?page=3>; rel=\"last\", <https://x/issues?page=2>; rel=\"next\"";
+        assert_eq!(next_link(link).as_deref(), Some("https://x/issues?page=2"));
+        assert_eq!(next_link("<https://x/issues?page=1>; rel=\"prev\""), None);
+        let (url, server) = serve(vec![
+            (
+                "200 OK",
+                vec![(
+                    "Link",
+                    String::from("<{url}/repos/hackia/breath/issues?page=2>; rel=\"next\""),
+                )],
+                r#"[{"number":1,"title":"Crash"},{"number":2,"title":"PR","pull_request":{}}]"#,
+            ),
+            ("200 OK", Vec::new(), r#"[{"number":3,"title":"Docs"}]"#),
+            ("404 Not Found", Vec::new(), r#"{"message":"Not Found"}"#),
+            (
+                "200 OK",
+                Vec::new(),
+                r#"{"number":7,"title":"Old","state":"closed"}"#,
+            ),
+        ]);
+        let github = GitHub {
+            url,
+            project: String::from("hackia/breath"),
+            token: Some(String::from("secret")),
+        };
+        let issues = github.open_issues().expect("issues");
+        assert_eq!(github.issue("404").expect("issue"), None);
+        assert!(github.issue("7").expect("issue").is_some_and(|i| i.closed));
+        let requests = server.join().expect("server");
+        assert_eq!(issues, [issue("1", "Crash"), issue("3", "Docs")]);
+        assert!(
+            requests[0].starts_with("get /repos/hackia/breath/issues?state=open&per_page=100 ")
+        );
+        assert!(requests[0].contains("authorization: bearer secret"));
+        assert!(requests[1].starts_with("get /repos/hackia/breath/issues?page=2 "));
+    }
+
+    #[test]
+    fn gitlab_follows_the_next_page_header() {
+        let (url, server) = serve(vec![
+            (
+                "200 OK",
+                vec![("X-Next-Page", String::from("2"))],
+                r#"[{"iid":1,"title":"Crash"}]"#,
+            ),
+            (
+                "200 OK",
+                vec![("X-Next-Page", String::new())],
+                r#"[{"iid":2,"title":"Docs"}]"#,
+            ),
+        ]);
+        let gitlab = GitLab {
+            url,
+            project: String::from("hackia/breath"),
+            token: Some(String::from("secret")),
+        };
+        let issues = gitlab.open_issues().expect("issues");
+        let requests = server.join().expect("server");
+        assert_eq!(issues, [issue("1", "Crash"), issue("2", "Docs")]);
+        assert!(requests[0].starts_with("get /api/v4/projects/hackia%2fbreath/issues?"));
+        assert!(requests[0].contains("private-token: secret"));
+        assert!(requests[1].contains("page=2"));
+    }
+
+    #[test]
+    fn jira_follows_the_next_page_token() {
+        let (url, server) = serve(vec![
+            (
+                "200 OK",
+                Vec::new(),
+                r#"{"nextPageToken":"abc","issues":[{"key":"BR-1","fields":{"summary":"Crash"}}]}"#,
+            ),
+            (
+                "200 OK",
+                Vec::new(),
+                r#"{"isLast":true,"issues":[{"key":"BR-2","fields":{"summary":"Docs"}}]}"#,
+            ),
+        ]);
+        let jira = Jira {
+            url,
+            project: String::from("BR"),
+            token: None,
+        };
+        let issues = jira.open_issues().expect("issues");
+        let requests = server.join().expect("server");
+        assert_eq!(issues, [issue("BR-1", "Crash"), issue("BR-2", "Docs")]);
+        assert!(requests[0].starts_with("get /rest/api/3/search/jql?"));
+        assert!(!requests[0].contains("nextpagetoken"));
+        assert!(requests[1].contains("nextpagetoken=abc"));
+    }
+
+    #[test]
+    fn local_reads_the_issue_file() {
+        let file = std::env::temp_dir().join(format!("breath-issues-{}.yml", std::process::id()));
+        std::fs::write(
+            &file,
+            "- id: \"12\"\n  title: Crash\n- id: \"13\"\n  title: Docs\n  closed: true\n",
+        )
+        .expect("write");
+        let local = Local {
+            file: file.display().to_string(),
+        };
+        assert_eq!(local.open_issues().expect("issues"), [issue("12", "Crash")]);
+        assert!(local.issue("13").expect("issue").is_some_and(|i| i.closed));
+        assert_eq!(local.issue("14").expect("issue"), None);
+        std::fs::remove_file(&file).expect("remove");
+        let missing = local.open_issues().expect_err("missing file");
+        assert!(missing.to_string().contains(local.file.as_str()));
+    }
+}
@@ -0,0 +1,200 @@
+use crate::commit::{Commit, branch_revset, messages, revision_files, staged_files};
+use crate::config::{Config, load_config};
+use crate::team::is_email;
+use crossterm::style::Stylize;
+use std::process::ExitCode;
+use std::str::FromStr;
+
+///
+/// Check a commit against the configuration
+///
+#[must_use]
+pub fn check(commit: &Commit, config: &Config, files: &[String]) -> Vec<String> {
+    let mut problems = Vec::new();
+    if !config
+        .types
+        .iter()
+        .any(|t| t.split('~').next().unwrap_or_default().trim() == commit.t)
+    {
+        problems.push(format!("unknown type `{}`", commit.t));
+    }
+    for scope in &commit.scopes {
+        if !config.scopes.contains(scope) {
+            problems.push(format!("unknown scope `{scope}`"));
+        }
+    }
+    let roles = config.roles();
+    for role in &commit.roles {
+        if !roles.iter().any(|r| r.name.eq(role)) {
+            problems.push(format!("unknown role `{role}`"));
+        }
+    }
+    if !config.paths.is_empty() && !files.is_empty() {
+        let (touched, _) = config.scopes_of(files);
+        for scope in &commit.scopes {
+            if config.paths.contains_key(scope) && !touched.contains(scope) {
+                problems.push(format!("scope `{scope}` matches none of the changed files"));
+            }
+        }
+        for scope in &touched {
+            if !commit.scopes.contains(scope) {
+                problems.push(format!("changed files of scope `{scope}` are not declared"));
+            }
+        }
+    }
+    problems
+}
+
+///
+/// Check the team roster of the configuration
+///
+#[must_use]
+pub fn check_team(config: &Config) -> Vec<String> {
+    let mut problems = Vec::new();
+    let roles = config.roles();
+    for member in &config.team {
+        if !member.email.is_empty() && !is_email(member.email.as_str()) {
+            problems.push(format!(
+                "invalid email `{}` for @{}",
+                member.email, member.handle
+            ));
+        }
+        for role in &member.roles {
+            if !roles.iter().any(|r| r.name.eq(role)) {
+                problems.push(format!("unknown role `{role}` for @{}", member.handle));
+            }
+        }
+    }
+    problems
+}
+
+fn report(id: &str, message: &str, config: &Config, files: &[String], strict: bool) -> bool {
+    let short = id.get(..12).unwrap_or(id);
+    let problems = match Commit::from_str(message.trim()) {
+        Ok(commit) => check(&commit, config, files),
+        Err(e) if !strict => {
+            println!("{} {}", "~".yellow(), format!("{short} {e}").dark_grey());
+            return true;
+        }
+        Err(e) => vec![e.to_string()],
+    };
+    for problem in &problems {
+        println!("{} {}", "!".red(), format!("{short} {problem}").yellow());
+    }
+    problems.is_empty()
+}
+
+///
+/// Lint a commit message file or the messages of a revset, the current branch by default
+///
+/// In a revset, a message that is not a breath one is only a warning unless `strict` is set.
+///
+/// # Panics
+/// if bad config
+///
+#[must_use]
+pub fn lint(revset: Option<&str>, file: Option<&str>, strict: bool) -> ExitCode {
+    let config = load_config().breathes;
+    let team = check_team(&config);
+    for problem in &team {
+        println!("{} {}", "!".red(), format!("breath.yml {problem}").yellow());
+    }
+    let mut valid = team.is_empty();
+    let mut count = 0;
+    if let Some(file) = file {
+        let Ok(message) = std::fs::read_to_string(file) else {
+            eprintln!("failed to read {file}");
+            return ExitCode::FAILURE;
+        };
+        count += 1;
+        valid &= report(file, message.as_str(), &config, &staged_files(), true);
+    } else {
+        let revset = revset.map_or_else(branch_revset, String::from);
+        for (id, message) in messages(revset.as_str()) {
+            count += 1;
+            valid &= report(
+                id.as_str(),
+                message.as_str(),
+                &config,
+                &revision_files(id.as_str()),
+                strict,
+            );
+        }
+    }
+    if valid {
+        println!(
+            "{} {}",
+            "✓".green(),
+            format!("{count} commit messages checked").dark_cyan()
+        );
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check;
+    use crate::commit::Commit;
+    use crate::config::Config;
+
+    fn config() -> Config {
+        let mut config = Config {
+            scopes: vec![String::from("core"), String::from("docs")],
+            types: vec![String::from("feat ~ a new feature"), String::from("fix")],
+            ..Config::default()
+        };
+        config
+            .paths
+            .insert(String::from("core"), vec![String::from("src/**")]);
+        config
+            .paths
+            .insert(String::from("docs"), vec![String::from("*.md")]);
+        config
+    }
+
+    fn commit(t: &str, scopes: &[&str], roles: &[&str]) -> Commit {
+        Commit {
+            t: t.to_string(),
+            scopes: scopes.iter().map(ToString::to_string).collect(),
+            roles: roles.iter().map(ToString::to_string).collect(),
+            ..Commit::default()
+        }
+    }
+
+    #[test]
+    fn reports_unknown_names() {
+        let files = [String::from("src/main.rs")];
+        assert!(
+            check(
+                &commit("feat", &["core"], &["Developer"]),
+                &config(),
+                &files
+            )
+            .is_empty()
+        );
+        let problems = check(&commit("wip", &["web"], &["Wizard"]), &config(), &[]);
+        assert_eq!(
+            problems,
+            [
+                "unknown type `wip`",
+                "unknown scope `web`",
+                "unknown role `Wizard`"
+            ]
+        );
+    }
+
+    #[test]
+    fn compares_the_scopes_with_the_changed_files() {
+        let files = [String::from("src/main.rs")];
+        let problems = check(&commit("fix", &["docs"], &[]), &config(), &files);
+        assert_eq!(
+            problems,
+            [
+                "scope `docs` matches none of the changed files",
+                "changed files of scope `core` are not declared"
+            ]
+        );
+    }
+}
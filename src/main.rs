@@ -8,17 +8,45 @@ pub mod config;
 pub mod doc;
 #[doc = "A module to get the tree of the current repository"]
 pub mod tree;
+#[doc = "A module to complete arguments from the repository in the shells"]
+pub mod complete;
+#[doc = "A module to discover scopes from the layout of the repository"]
+pub mod discover;
+#[doc = "A module to describe the questions asked for each commit type"]
+pub mod flow;
+#[doc = "A module to display the breath commits of the history"]
+pub mod history;
+#[doc = "A module to initialize breath in a repository"]
+pub mod init;
+#[doc = "A module to fetch and validate issues from the tracker"]
+pub mod issue;
+#[doc = "A module to check commit messages against the configuration"]
+pub mod lint;
+#[doc = "A module to run the documentation steps without a shell"]
+pub mod pipeline;
+#[doc = "A module to resolve the author identity and the team roster"]
+pub mod team;
+#[doc = "A module to show the repository in a full-screen terminal view"]
+pub mod zen;
 
-use crate::commit::{Commit, add, run_commit, vcs};
-use crate::config::init_config;
+use crate::commit::{
+    Commit, add, discard_draft, draft_path, resume_draft, rewrite_draft_path, run_commit, vcs,
+};
+use crate::complete::{SHELLS, branches, completions, scopes, types};
+use crate::config::{migrate, schema};
 use crate::doc::{generate_doc, generate_man};
-use crate::utils::{call, zen};
+use crate::history::{FORMATS, Filter, log};
+use crate::init::init;
+use crate::lint::lint;
+use crate::utils::{call, set_verbose, translate};
+use crate::zen::zen;
 use breathes::hooks::run_hooks;
-use clap::{Arg, Command};
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command};
+use clap_complete::{ArgValueCandidates, CompleteEnv};
 use crossterm::cursor::MoveTo;
 use crossterm::execute;
 use crossterm::terminal::{Clear, ClearType};
-use std::io::stdout;
+use std::io::{IsTerminal, stdout};
 use std::process::ExitCode;
 
 fn breathes() -> Command {
@@ -26,7 +54,36 @@ fn breathes() -> Command {
         .version(env!("CARGO_PKG_VERSION"))
         .author("hackia <dev@hackia.org>")
         .about("A tool for managing Git and Mercurial repositories")
-        .subcommand(Command::new("init").about("Initialize a new breath repository"))
+        .arg(
+            Arg::new("verbose")
+                .long("verbose")
+                .short('v')
+                .global(true)
+                .help("Stream the output of the commands instead of keeping it in .breath/logs")
+                .action(ArgAction::SetTrue),
+        )
+        .subcommand(
+            Command::new("init")
+                .about("Initialize a new breath repository")
+                .arg(
+                    Arg::new("force")
+                        .long("force")
+                        .help("Overwrite an existing configuration")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("defaults")
+                        .long("defaults")
+                        .help("Write the detected configuration without asking")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("vcs")
+                        .long("vcs")
+                        .value_parser(["git", "hg"])
+                        .help("Initialize a repository of this VCS when none exists"),
+                ),
+        )
         .subcommand(Command::new("add").about("Add files to the index"))
         .subcommand(
             Command::new("rm").about("Remove files from the working tree and from the index"),
@@ -46,39 +103,262 @@ fn breathes() -> Command {
         )
         .subcommand(Command::new("describe").about("Show information about commits"))
         .subcommand(
-            Command::new("config").about("Manage configuration").arg(
-                Arg::new("vcs")
-                    .value_names(["git", "hg"])
-                    .default_missing_value("git")
-                    .default_value("git")
-                    .required(true),
-            ),
+            Command::new("config")
+                .about("Manage configuration")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("migrate")
+                        .about("Upgrade the configuration to the current version")
+                        .arg(
+                            Arg::new("yes")
+                                .long("yes")
+                                .short('y')
+                                .help("Write the changes without asking")
+                                .action(ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("schema").about("Print the JSON Schema of the configuration"),
+                ),
         )
         .subcommand(
             Command::new("gen")
                 .about("Generators for documentation and manuals")
-                .subcommand(Command::new("man").about("Generate man pages"))
-                .subcommand(Command::new("doc").about("Generate documentation")),
+                .subcommand(
+                    Command::new("man")
+                        .about("Generate man pages")
+                        .arg(Arg::new("output").long("output").short('o').help(
+                            "The directory receiving the pages, documentation.man_dir by default",
+                        ))
+                        .arg(
+                            Arg::new("force")
+                                .long("force")
+                                .help("Run every step, even those whose inputs are unchanged")
+                                .action(ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("doc").about("Generate documentation").arg(
+                        Arg::new("force")
+                            .long("force")
+                            .help("Run every step, even those whose inputs are unchanged")
+                            .action(ArgAction::SetTrue),
+                    ),
+                )
+                .subcommand(
+                    Command::new("completions")
+                        .about("Generate the shell completions")
+                        .arg(
+                            Arg::new("shell")
+                                .value_parser(SHELLS)
+                                .required(true)
+                                .help("The shell to register the completions in"),
+                        ),
+                ),
         )
         .subcommand(Command::new("health").about("Verify repository health"))
-        .subcommand(Command::new("commit").about("Commit changes to the repository"))
-        .subcommand(Command::new("push").about("Push changes to remote repositories"))
-        .subcommand(Command::new("pull").about("Pull changes from remote repositories"))
-        .subcommand(Command::new("status").about("Show the status of the repository"))
-        .subcommand(Command::new("zen").about("display a loop menu to interact with breath"))
-        .subcommand(Command::new("log").about("Show the commit log"))
         .subcommand(
-            Command::new("diff")
-                .about("Show the changes between the working directory and the index"),
+            Command::new("lint")
+                .about("Check commit messages against the configuration")
+                .arg(
+                    Arg::new("revset")
+                        .add(ArgValueCandidates::new(branches))
+                        .help("The revisions to check, the current branch by default"),
+                )
+                .arg(
+                    Arg::new("file")
+                        .long("file")
+                        .conflicts_with("revset")
+                        .help("Check a commit message file, for a commit-msg hook"),
+                )
+                .arg(
+                    Arg::new("strict")
+                        .long("strict")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("file")
+                        .help("Fail on messages that are not breath ones instead of warning"),
+                ),
         )
+        .subcommand(
+            Command::new("commit")
+                .about("Commit changes to the repository")
+                .arg(
+                    Arg::new("resume")
+                        .long("resume")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("discard")
+                        .help("Resume the commit wizard from the saved draft"),
+                )
+                .arg(
+                    Arg::new("discard")
+                        .long("discard")
+                        .action(ArgAction::SetTrue)
+                        .help("Drop the saved commit and rewrite drafts"),
+                )
+                .arg(
+                    Arg::new("amend")
+                        .long("amend")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with_all(["resume", "discard"])
+                        .help("Rewrite the message of the last commit through the wizard"),
+                )
+                .arg(
+                    Arg::new("type")
+                        .long("type")
+                        .short('t')
+                        .conflicts_with_all(["resume", "discard", "amend"])
+                        .add(ArgValueCandidates::new(types))
+                        .help("Preselect the commit type"),
+                )
+                .arg(
+                    Arg::new("scope")
+                        .long("scope")
+                        .short('s')
+                        .action(ArgAction::Append)
+                        .conflicts_with_all(["resume", "discard", "amend"])
+                        .add(ArgValueCandidates::new(scopes))
+                        .help("Preselect a scope, can be repeated"),
+                ),
+        )
+        .subcommand(
+            Command::new("reword")
+                .about("Rewrite the message of an existing commit through the wizard")
+                .arg(
+                    Arg::new("rev")
+                        .required(true)
+                        .add(ArgValueCandidates::new(branches))
+                        .help("The revision to reword"),
+                ),
+        )
+        .subcommand(proxy("push", "Push changes to remote repositories"))
+        .subcommand(proxy("pull", "Pull changes from remote repositories"))
+        .subcommand(proxy("status", "Show the status of the repository"))
+        .subcommand(Command::new("zen").about("Show the repository in a full-screen view driven by single keys"))
+        .subcommand(
+            proxy("log", "Show the breath commits of the history")
+                .arg(
+                    Arg::new("rev")
+                        .long("rev")
+                        .add(ArgValueCandidates::new(branches))
+                        .help("The revisions to show, the history of the working directory by default"),
+                )
+                .arg(
+                    Arg::new("type")
+                        .long("type")
+                        .action(ArgAction::Append)
+                        .add(ArgValueCandidates::new(types))
+                        .help("Show only the commits of this type"),
+                )
+                .arg(
+                    Arg::new("scope")
+                        .long("scope")
+                        .action(ArgAction::Append)
+                        .add(ArgValueCandidates::new(scopes))
+                        .help("Show only the commits touching this scope"),
+                )
+                .arg(
+                    Arg::new("role")
+                        .long("role")
+                        .action(ArgAction::Append)
+                        .help("Show only the commits written in this role"),
+                )
+                .arg(
+                    Arg::new("breaking")
+                        .long("breaking")
+                        .action(ArgAction::SetTrue)
+                        .help("Show only the commits with breaking changes"),
+                )
+                .arg(
+                    Arg::new("resolves")
+                        .long("resolves")
+                        .help("Show only the commits resolving this issue"),
+                )
+                .arg(
+                    Arg::new("since")
+                        .long("since")
+                        .help("Show only the commits after this date"),
+                )
+                .arg(
+                    Arg::new("limit")
+                        .short('n')
+                        .long("limit")
+                        .value_parser(clap::value_parser!(usize))
+                        .help("Show at most this number of commits"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(FORMATS)
+                        .default_value("table")
+                        .help("Print the commits as a table, JSON or CSV"),
+                )
+                .group(
+                    ArgGroup::new("view")
+                        .args(FILTERS)
+                        .multiple(true)
+                        .conflicts_with("args"),
+                ),
+        )
+        .subcommand(proxy(
+            "diff",
+            "Show the changes between the working directory and the index",
+        ))
+}
+
+const FILTERS: [&str; 8] = [
+    "rev", "type", "scope", "role", "breaking", "resolves", "since", "format",
+];
+
+///
+/// The arguments of a proxied command, with the `-n` limit that only `log` defines
+///
+fn forwarded(cmd: &str, args: &ArgMatches) -> Vec<String> {
+    let mut forwarded = if cmd.eq("log") {
+        args.get_one::<usize>("limit")
+            .map(|limit| vec![String::from("-n"), limit.to_string()])
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    forwarded.extend(args.get_many::<String>("args").unwrap_or_default().cloned());
+    forwarded
+}
+
+fn forward(cmd: &str, args: &[String]) -> ExitCode {
+    let vcs = vcs();
+    let argv = translate(vcs.as_str(), cmd, args);
+    match call(
+        vcs.as_str(),
+        &argv.iter().map(String::as_str).collect::<Vec<&str>>(),
+    ) {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn proxy(name: &'static str, about: &'static str) -> Command {
+    Command::new(name).about(about).arg(
+        Arg::new("args")
+            .num_args(0..)
+            .trailing_var_arg(true)
+            .allow_hyphen_values(true)
+            .help("Arguments passed to the VCS as they are"),
+    )
 }
 
 #[must_use]
 pub fn main() -> ExitCode {
-    execute!(stdout(), Clear(ClearType::All), MoveTo(0, 0)).expect("failed to clear screen");
+    CompleteEnv::with_factory(breathes).complete();
+    if stdout().is_terminal() {
+        execute!(stdout(), Clear(ClearType::All), MoveTo(0, 0)).expect("failed to clear screen");
+    }
     let mut commit = Commit::new();
     let app = breathes();
     let matches = app.get_matches();
+    set_verbose(matches.get_flag("verbose"));
     match matches.subcommand() {
         Some(("health", _)) => {
             if run_hooks().is_err() {
@@ -87,17 +367,84 @@ pub fn main() -> ExitCode {
                 ExitCode::SUCCESS
             }
         }
-        Some(("zen", _)) => {
-            if zen().is_err() {
+        Some(("lint", sub_matches)) => lint(
+            sub_matches.get_one::<String>("revset").map(String::as_str),
+            sub_matches.get_one::<String>("file").map(String::as_str),
+            sub_matches.get_flag("strict"),
+        ),
+        Some(("zen", _)) => match zen() {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("zen failed: {e}");
                 ExitCode::FAILURE
+            }
+        },
+        Some(("commit", sub_matches)) => {
+            if sub_matches.get_flag("discard") {
+                return if discard_draft(draft_path().as_path()).is_ok()
+                    && discard_draft(rewrite_draft_path().as_path()).is_ok()
+                {
+                    ExitCode::SUCCESS
+                } else {
+                    ExitCode::FAILURE
+                };
+            }
+            let amend = sub_matches.get_flag("amend");
+            let mut resume = sub_matches.get_flag("resume") && !amend;
+            if amend {
+                let head = if vcs().eq("hg") { "." } else { "HEAD" };
+                match Commit::rewriting(head) {
+                    Ok((c, resumed)) => {
+                        commit = c;
+                        resume = resumed;
+                    }
+                    Err(e) => {
+                        eprintln!("{e}");
+                        return ExitCode::FAILURE;
+                    }
+                }
             } else {
-                ExitCode::SUCCESS
+                let path = draft_path();
+                if !resume && path.is_file() {
+                    match resume_draft(path.as_path(), "A commit draft exists") {
+                        Ok(answer) => resume = answer,
+                        Err(e) => {
+                            eprintln!("{e}");
+                            return ExitCode::FAILURE;
+                        }
+                    }
+                }
+                if resume {
+                    let Some(draft) = Commit::load_draft(path.as_path()) else {
+                        eprintln!("no commit draft to resume");
+                        return ExitCode::FAILURE;
+                    };
+                    commit = draft;
+                }
+            }
+            if let Some(t) = sub_matches.get_one::<String>("type") {
+                commit.t.clone_from(t);
+            }
+            if let Some(scopes) = sub_matches.get_many::<String>("scope") {
+                commit.scopes = scopes.cloned().collect();
             }
-        }
-        Some(("commit", _)) => {
             if run_hooks().is_ok() {
-                commit.commit().map_or(ExitCode::FAILURE, |c| {
+                let wizard = if resume {
+                    commit.resume()
+                } else if amend {
+                    commit.edit()
+                } else {
+                    commit.commit()
+                };
+                wizard.map_or(ExitCode::FAILURE, |c| {
                     if run_commit(c).is_err() {
+                        if amend {
+                            eprintln!("commit failed, run `breath commit --amend` to retry");
+                        } else {
+                            eprintln!("commit failed, run `breath commit --resume` to retry");
+                        }
+                        ExitCode::FAILURE
+                    } else if discard_draft(c.draft_path().as_path()).is_err() {
                         ExitCode::FAILURE
                     } else {
                         ExitCode::SUCCESS
@@ -107,6 +454,29 @@ pub fn main() -> ExitCode {
                 ExitCode::FAILURE
             }
         }
+        Some(("reword", sub_matches)) => {
+            let rev = sub_matches
+                .get_one::<String>("rev")
+                .map_or("HEAD", String::as_str);
+            match Commit::rewriting(rev) {
+                Ok((mut c, resumed)) => {
+                    if resumed { c.resume() } else { c.edit() }.map_or(ExitCode::FAILURE, |c| {
+                        if run_commit(c).is_err() {
+                            eprintln!("reword failed, run `breath reword {rev}` to retry");
+                            ExitCode::FAILURE
+                        } else if discard_draft(c.draft_path().as_path()).is_err() {
+                            ExitCode::FAILURE
+                        } else {
+                            ExitCode::SUCCESS
+                        }
+                    })
+                }
+                Err(e) => {
+                    eprintln!("{e}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
         Some(("add", _)) => {
             if add().is_ok() {
                 ExitCode::SUCCESS
@@ -114,27 +484,118 @@ pub fn main() -> ExitCode {
                 ExitCode::FAILURE
             }
         }
-        Some(("init", _)) => {
-            if init_config().is_ok() {
+        Some(("init", args)) => match init(
+            args.get_flag("force"),
+            args.get_flag("defaults"),
+            args.get_one::<String>("vcs").map(String::as_str),
+        ) {
+            Ok(()) => {
                 println!("breath initialized successfully");
                 ExitCode::SUCCESS
-            } else {
-                eprintln!("failed to initialize breath");
-                ExitCode::FAILURE
             }
-        }
-        Some((cmd @ ("push" | "pull" | "status" | "log" | "diff"), _)) => {
-            if call(vcs().as_str(), cmd).is_ok() {
-                ExitCode::SUCCESS
-            } else {
+            Err(e) => {
+                eprintln!("failed to initialize breath: {e}");
                 ExitCode::FAILURE
             }
+        },
+        Some(("log", args)) if !args.contains_id("args") => {
+            let strings = |id: &str| {
+                args.get_many::<String>(id)
+                    .unwrap_or_default()
+                    .cloned()
+                    .collect::<Vec<String>>()
+            };
+            let filter = Filter {
+                types: strings("type"),
+                scopes: strings("scope"),
+                roles: strings("role"),
+                breaking: args.get_flag("breaking"),
+                resolves: args.get_one::<String>("resolves").cloned(),
+                since: args.get_one::<String>("since").cloned(),
+                limit: args.get_one::<usize>("limit").copied(),
+            };
+            let format = args
+                .get_one::<String>("format")
+                .map_or("table", String::as_str);
+            match log(
+                args.get_one::<String>("rev").map(String::as_str),
+                &filter,
+                format,
+            ) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("failed to print the log: {e}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Some((cmd @ ("push" | "pull" | "status" | "log" | "diff"), args)) => {
+            forward(cmd, &forwarded(cmd, args))
         }
+        Some(("config", sub_matches)) => match sub_matches.subcommand() {
+            Some(("migrate", args)) => match migrate(args.get_flag("yes")) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("failed to migrate the configuration: {e}");
+                    ExitCode::FAILURE
+                }
+            },
+            Some(("schema", _)) => match schema() {
+                Ok(schema) => {
+                    println!("{schema}");
+                    ExitCode::SUCCESS
+                }
+                Err(e) => {
+                    eprintln!("failed to generate the schema: {e}");
+                    ExitCode::FAILURE
+                }
+            },
+            _ => ExitCode::FAILURE,
+        },
         Some(("gen", sub_matches)) => match sub_matches.subcommand() {
-            Some(("man", _)) => generate_man(),
-            Some(("doc", _)) => generate_doc(),
+            Some(("man", args)) => generate_man(
+                breathes(),
+                args.get_one::<String>("output").map(String::as_str),
+                args.get_flag("force"),
+            ),
+            Some(("doc", args)) => generate_doc(args.get_flag("force")),
+            Some(("completions", args)) => {
+                let shell = args.get_one::<String>("shell").map_or("", String::as_str);
+                if let Err(e) = completions(shell, &mut stdout()) {
+                    eprintln!("failed to generate the completions: {e}");
+                    return ExitCode::FAILURE;
+                }
+                ExitCode::SUCCESS
+            }
             _ => ExitCode::FAILURE,
         },
         _ => ExitCode::FAILURE,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{breathes, forwarded};
+
+    fn proxied(argv: &[&str]) -> Vec<String> {
+        let matches = breathes()
+            .try_get_matches_from(argv)
+            .expect("valid command line");
+        let (cmd, args) = matches.subcommand().expect("a subcommand");
+        forwarded(cmd, args)
+    }
+
+    #[test]
+    fn forwards_the_trailing_args() {
+        assert_eq!(proxied(&["breath", "status", "-s"]), ["-s"]);
+        assert_eq!(
+            proxied(&["breath", "diff", "HEAD~1", "--stat"]),
+            ["HEAD~1", "--stat"]
+        );
+        assert_eq!(proxied(&["breath", "push"]), Vec::<String>::new());
+        assert_eq!(
+            proxied(&["breath", "log", "-n", "3", "-p"]),
+            ["-n", "3", "-p"]
+        );
+    }
+}
@@ -0,0 +1,758 @@
+use crate::config::Documentation;
+use crate::utils::{breath_dir, capture};
+use crossterm::style::Stylize;
+use globset::Glob;
+use ignore::WalkBuilder;
+use inquire::Confirm;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::io::{Error, IsTerminal};
+use std::num::NonZeroUsize;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+///
+/// The programs which only write the paths given as operands
+///
+const WRITERS: [&str; 6] = ["rm", "cp", "mv", "mkdir", "touch", "ln"];
+
+#[derive(Deserialize, Serialize, JsonSchema, Default, Debug, Clone, PartialEq, Eq)]
+#[doc = "A documentation step, run without a shell"]
+pub struct Task {
+    /// The name other steps use in `needs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The program to run, which must be listed in `documentation.allow`
+    pub program: String,
+    /// The arguments given to the program
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// The working directory, relative to the repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// The environment variables set for the program
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// The paths the step may write, relative to the repository
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outputs: Vec<String>,
+    /// The globs of the files the step reads, relative to the repository, to skip it when unchanged
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<String>,
+    /// The names of the steps to run before this one, the previous step when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub needs: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+#[doc = "A documentation step, as a command line split on whitespace or in the structured form"]
+pub enum Entry {
+    Line(String),
+    Task(Task),
+}
+
+impl Entry {
+    ///
+    /// The structured form of the step
+    ///
+    #[must_use]
+    pub fn task(&self) -> Task {
+        match self {
+            Self::Line(line) => {
+                let mut words = line.split_whitespace().map(String::from);
+                Task {
+                    program: words.next().unwrap_or_default(),
+                    args: words.collect(),
+                    ..Task::default()
+                }
+            }
+            Self::Task(task) => task.clone(),
+        }
+    }
+
+    ///
+    /// The structured form of a command line writing files, with the paths it writes as outputs
+    ///
+    /// `None` for structured steps, for programs writing nothing and for paths outside the repository.
+    ///
+    #[must_use]
+    pub fn with_outputs(&self) -> Option<Task> {
+        let Self::Line(_) = self else {
+            return None;
+        };
+        let mut task = self.task();
+        let outputs = task
+            .written()
+            .ok()?
+            .into_iter()
+            .map(|operand| {
+                normalize(Path::new(""), operand).map(|path| path.to_string_lossy().to_string())
+            })
+            .collect::<Option<Vec<String>>>()?;
+        if outputs.is_empty() {
+            return None;
+        }
+        task.outputs = outputs;
+        Some(task)
+    }
+}
+
+impl Display for Task {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.program)?;
+        for arg in &self.args {
+            write!(f, " {arg}")?;
+        }
+        if let Some(cwd) = &self.cwd {
+            write!(f, " (in {cwd})")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.task())
+    }
+}
+
+#[must_use]
+pub fn default_allow() -> Vec<String> {
+    [
+        "cargo",
+        "doxygen",
+        "mdbook",
+        "sphinx-build",
+        "pandoc",
+        "rm",
+        "cp",
+        "mkdir",
+        "touch",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+///
+/// Resolve a relative path against a base, refusing absolute paths and paths leaving the repository
+///
+fn normalize(base: &Path, path: &str) -> Option<PathBuf> {
+    let mut normalized = base.to_path_buf();
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(normalized)
+}
+
+impl Task {
+    ///
+    /// Check the step against the allowlist and its declared outputs
+    ///
+    /// # Errors
+    /// If the program is not allowed, or the step leaves the repository or writes outside its outputs
+    pub fn check(&self, allow: &[String]) -> Result<(), Error> {
+        if !allow.contains(&self.program) {
+            return Err(Error::other(format!(
+                "`{}` is not listed in documentation.allow",
+                self.program
+            )));
+        }
+        let cwd = self.cwd.as_deref().unwrap_or(".");
+        let base = normalize(Path::new(""), cwd).ok_or_else(|| {
+            Error::other(format!("working directory {cwd} is outside the repository"))
+        })?;
+        let outputs = self
+            .outputs
+            .iter()
+            .map(|output| {
+                normalize(Path::new(""), output).ok_or_else(|| {
+                    Error::other(format!("output {output} is outside the repository"))
+                })
+            })
+            .collect::<Result<Vec<PathBuf>, Error>>()?;
+        for operand in self.written()? {
+            if !normalize(&base, operand)
+                .is_some_and(|path| outputs.iter().any(|output| path.starts_with(output)))
+            {
+                return Err(Error::other(format!(
+                    "`{self}` writes {operand} outside its outputs"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// Check if the program is one of the writers whose operands are checked against the outputs
+    ///
+    #[must_use]
+    pub fn is_writer(&self) -> bool {
+        WRITERS.contains(&self.program.as_str())
+    }
+
+    ///
+    /// The operands the step writes: the last one for `cp`, `mv` and `ln`, every one for the other writers
+    ///
+    /// The values of options such as `mkdir -m 755` or `touch -d 2020-01-01` are not operands.
+    ///
+    /// # Errors
+    /// If `cp`, `mv` or `ln` give their target with `-t` or `--target-directory`
+    fn written(&self) -> Result<Vec<&String>, Error> {
+        if !self.is_writer() {
+            return Ok(Vec::new());
+        }
+        let (shorts, longs): (&str, &[&str]) = match self.program.as_str() {
+            "mkdir" => ("m", &["--mode"]),
+            "touch" => ("dtr", &["--date", "--reference"]),
+            "cp" => ("S", &["--suffix", "--sparse", "--no-preserve"]),
+            "mv" | "ln" => ("S", &["--suffix"]),
+            _ => ("", &[]),
+        };
+        let mut operands = Vec::new();
+        let mut options = true;
+        let mut args = self.args.iter();
+        while let Some(arg) = args.next() {
+            if !options || !arg.starts_with('-') || arg.eq("-") {
+                operands.push(arg);
+            } else if arg.eq("--") {
+                options = false;
+            } else if longs.contains(&arg.as_str())
+                || (!arg.starts_with("--")
+                    && arg
+                        .chars()
+                        .skip(1)
+                        .position(|c| shorts.contains(c))
+                        .is_some_and(|at| at == arg.len() - 2))
+            {
+                args.next();
+            }
+        }
+        if !matches!(self.program.as_str(), "cp" | "mv" | "ln") {
+            return Ok(operands);
+        }
+        if self.args.iter().any(|arg| {
+            arg.starts_with("--target-directory")
+                || (arg.starts_with('-') && !arg.starts_with("--") && arg.contains('t'))
+        }) {
+            return Err(Error::other(format!(
+                "`{self}` gives its target with -t, give it as the last operand instead"
+            )));
+        }
+        Ok(operands.last().copied().into_iter().collect())
+    }
+
+    ///
+    /// The name of the step, or its command line
+    ///
+    #[must_use]
+    pub fn label(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.to_string())
+    }
+
+    ///
+    /// Run the step, keeping its output in `.breath/logs` unless verbose
+    ///
+    /// # Errors
+    /// If the program cannot be started or fails
+    pub fn execute(&self) -> Result<(), Error> {
+        capture(
+            Command::new(self.program.as_str())
+                .args(&self.args)
+                .current_dir(self.cwd.as_deref().unwrap_or("."))
+                .envs(&self.env),
+            self.label().as_str(),
+        )
+    }
+
+    fn digest(&self) -> Result<String, Error> {
+        let mut hasher = Sha256::new();
+        hasher.update(serde_json::to_vec(self).map_err(Error::other)?);
+        for file in input_files(&self.inputs) {
+            hasher.update(file.to_string_lossy().as_bytes());
+            hasher.update(std::fs::read(&file)?);
+        }
+        Ok(hex(&hasher.finalize()))
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+///
+/// The files matching the input globs, walking only the directories before the first wildcard
+///
+/// The `.git`, `.hg` and `.breath` directories are never walked. A glob starting with a wildcard also skips the files
+/// ignored by `.gitignore` and `.ignore`, while one naming a directory, such as `target/doc/**`, walks all of it.
+///
+fn input_files(patterns: &[String]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for pattern in patterns {
+        let Ok(glob) = Glob::new(pattern) else {
+            continue;
+        };
+        let matcher = glob.compile_matcher();
+        let root = Path::new(pattern)
+            .components()
+            .take_while(|part| {
+                !part
+                    .as_os_str()
+                    .to_string_lossy()
+                    .contains(['*', '?', '[', '{'])
+            })
+            .collect::<PathBuf>();
+        let wide = root.as_os_str().is_empty();
+        let root = if wide { PathBuf::from(".") } else { root };
+        for entry in WalkBuilder::new(root)
+            .standard_filters(false)
+            .git_ignore(wide)
+            .git_exclude(wide)
+            .ignore(wide)
+            .require_git(false)
+            .filter_entry(|entry| {
+                !(entry.file_type().is_some_and(|t| t.is_dir())
+                    && [".git", ".hg", ".breath"]
+                        .contains(&entry.file_name().to_string_lossy().as_ref()))
+            })
+            .build()
+            .flatten()
+        {
+            let path = entry.path().strip_prefix(".").unwrap_or(entry.path());
+            if entry.file_type().is_some_and(|t| t.is_file())
+                && (matcher.is_match(path) || path.starts_with(pattern))
+            {
+                files.push(path.to_path_buf());
+            }
+        }
+    }
+    files.sort();
+    files.dedup();
+    files
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[doc = "The outcome of a step"]
+pub enum Status {
+    Ran,
+    Cached,
+    Failed,
+    Blocked,
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ran => write!(f, "ran"),
+            Self::Cached => write!(f, "cached"),
+            Self::Failed => write!(f, "failed"),
+            Self::Blocked => write!(f, "blocked"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[doc = "The outcome and the duration of a step"]
+pub struct Report {
+    pub label: String,
+    pub status: Status,
+    pub time: Duration,
+}
+
+///
+/// The steps each step waits for, checking the names and refusing duplicates and cycles
+///
+fn dependencies(tasks: &[Task]) -> Result<Vec<Vec<usize>>, Error> {
+    for (i, task) in tasks.iter().enumerate() {
+        if let Some(name) = &task.name
+            && tasks[..i].iter().any(|t| t.name.as_ref() == Some(name))
+        {
+            return Err(Error::other(format!("step name `{name}` is used twice")));
+        }
+    }
+    let mut dependencies = Vec::new();
+    for (i, task) in tasks.iter().enumerate() {
+        let Some(needs) = &task.needs else {
+            dependencies.push(i.checked_sub(1).into_iter().collect());
+            continue;
+        };
+        let mut needed = Vec::new();
+        for need in needs {
+            needed.push(
+                tasks
+                    .iter()
+                    .position(|t| t.name.as_deref() == Some(need.as_str()))
+                    .ok_or_else(|| Error::other(format!("unknown step `{need}` in needs")))?,
+            );
+        }
+        dependencies.push(needed);
+    }
+    let mut ordered = vec![false; tasks.len()];
+    while let Some(i) =
+        (0..tasks.len()).find(|&i| !ordered[i] && dependencies[i].iter().all(|&d| ordered[d]))
+    {
+        ordered[i] = true;
+    }
+    if let Some(i) = ordered.iter().position(|done| !done) {
+        return Err(Error::other(format!(
+            "step `{}` depends on itself through its needs",
+            tasks[i].label()
+        )));
+    }
+    Ok(dependencies)
+}
+
+const CACHE_PATH: &str = ".breath/cache.yml";
+
+///
+/// The cache entry of a step, its position telling apart the steps sharing a command line
+///
+fn cache_key(index: usize, task: &Task) -> String {
+    format!("{index}:{}", task.label())
+}
+
+///
+/// Run the steps in parallel once their needs are done, skipping those whose inputs are unchanged
+///
+/// A step whose inputs cannot be read fails without stopping the others.
+///
+/// # Errors
+/// If two steps share a name, a step needs an unknown or cyclic step, or the cache cannot be written
+pub fn run(tasks: &[Task], force: bool) -> Result<Vec<Report>, Error> {
+    let dependencies = dependencies(tasks)?;
+    let mut cache = std::fs::read_to_string(CACHE_PATH)
+        .ok()
+        .and_then(|content| serde_yaml::from_str::<BTreeMap<String, String>>(&content).ok())
+        .unwrap_or_default();
+    let workers = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let mut status: Vec<Option<Status>> = vec![None; tasks.len()];
+    let mut times = vec![Duration::ZERO; tasks.len()];
+    let mut started = vec![false; tasks.len()];
+    let (sender, receiver) = mpsc::channel();
+    std::thread::scope(|scope| -> Result<(), Error> {
+        let mut running = 0;
+        loop {
+            for i in 0..tasks.len() {
+                if !started[i]
+                    && dependencies[i]
+                        .iter()
+                        .any(|&d| matches!(status[d], Some(Status::Failed | Status::Blocked)))
+                {
+                    started[i] = true;
+                    status[i] = Some(Status::Blocked);
+                    println!(
+                        "{} {}",
+                        "!".red(),
+                        format!("{} blocked by a failed step", tasks[i].label()).yellow()
+                    );
+                }
+            }
+            for i in 0..tasks.len() {
+                if running >= workers {
+                    break;
+                }
+                if started[i] || !dependencies[i].iter().all(|&d| status[d].is_some()) {
+                    continue;
+                }
+                started[i] = true;
+                let task = &tasks[i];
+                let digest = match task.digest() {
+                    Ok(digest) => digest,
+                    Err(e) => {
+                        status[i] = Some(Status::Failed);
+                        println!(
+                            "{} {}",
+                            "!".red(),
+                            format!("{} cannot read its inputs: {e}", task.label()).yellow()
+                        );
+                        continue;
+                    }
+                };
+                if !force
+                    && !task.inputs.is_empty()
+                    && task.outputs.iter().all(|output| Path::new(output).exists())
+                    && cache
+                        .get(&cache_key(i, task))
+                        .is_some_and(|known| known.eq(&digest))
+                {
+                    status[i] = Some(Status::Cached);
+                    println!(
+                        "{} {}",
+                        "✓".green(),
+                        format!("{} is up to date", task.label()).dark_cyan()
+                    );
+                    continue;
+                }
+                running += 1;
+                let sender = sender.clone();
+                scope.spawn(move || {
+                    let start = Instant::now();
+                    let result = task.execute();
+                    let _ = sender.send((i, result, start.elapsed(), digest));
+                });
+            }
+            if running == 0 {
+                if started.iter().all(|done| *done) {
+                    return Ok(());
+                }
+                continue;
+            }
+            let (i, result, time, digest) = receiver.recv().map_err(Error::other)?;
+            running -= 1;
+            times[i] = time;
+            let label = tasks[i].label();
+            match result {
+                Ok(()) => {
+                    status[i] = Some(Status::Ran);
+                    cache.insert(cache_key(i, &tasks[i]), digest);
+                    println!(
+                        "{} {}",
+                        "✓".green(),
+                        format!("{label} ({:.1}s)", time.as_secs_f64()).dark_cyan()
+                    );
+                }
+                Err(e) => {
+                    status[i] = Some(Status::Failed);
+                    cache.remove(&cache_key(i, &tasks[i]));
+                    println!("{} {}", "!".red(), e.to_string().yellow());
+                }
+            }
+        }
+    })?;
+    breath_dir()?;
+    std::fs::write(
+        CACHE_PATH,
+        serde_yaml::to_string(&cache).map_err(Error::other)?,
+    )?;
+    Ok(tasks
+        .iter()
+        .zip(status.into_iter().zip(times))
+        .map(|(task, (status, time))| Report {
+            label: task.label(),
+            status: status.unwrap_or(Status::Blocked),
+            time,
+        })
+        .collect())
+}
+
+///
+/// Print the outcome and the duration of every step, then the elapsed time
+///
+pub fn summary(reports: &[Report], elapsed: Duration) {
+    let width = reports
+        .iter()
+        .map(|report| report.label.chars().count())
+        .max()
+        .unwrap_or_default()
+        .max(4);
+    println!("\n  {:<width$}  {:<7}  {:>8}", "step", "status", "time");
+    for report in reports {
+        let line = format!(
+            "  {:<width$}  {:<7}  {:>7.1}s",
+            report.label,
+            report.status.to_string(),
+            report.time.as_secs_f64()
+        );
+        match report.status {
+            Status::Ran | Status::Cached => println!("{}", line.dark_cyan()),
+            Status::Failed | Status::Blocked => println!("{}", line.yellow()),
+        }
+    }
+    println!(
+        "  {:<width$}  {:<7}  {:>7.1}s",
+        "total",
+        "",
+        elapsed.as_secs_f64()
+    );
+}
+
+fn trust_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .map(|dir| dir.join("breath").join("trusted.yml"))
+}
+
+///
+/// Ask once per repository, and again after every change, whether its documentation steps may run
+///
+/// A non-empty `BREATH_TRUST` environment variable trusts the steps without asking, for CI.
+///
+/// # Errors
+/// If the trust file cannot be read or written, on bad user inputs, or without a terminal to ask on
+pub fn trust(documentation: &Documentation) -> Result<bool, Error> {
+    if std::env::var_os("BREATH_TRUST").is_some_and(|value| !value.is_empty()) {
+        return Ok(true);
+    }
+    let digest = hex(&Sha256::digest(
+        serde_json::to_vec(documentation).map_err(Error::other)?,
+    ));
+    let repository = std::env::current_dir()?
+        .canonicalize()?
+        .display()
+        .to_string();
+    let path = trust_path().ok_or_else(|| Error::other("no configuration directory"))?;
+    let mut trusted = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_yaml::from_str::<BTreeMap<String, String>>(&content).ok())
+        .unwrap_or_default();
+    if trusted
+        .get(&repository)
+        .is_some_and(|known| known.eq(&digest))
+    {
+        return Ok(true);
+    }
+    if !std::io::stdin().is_terminal() {
+        return Err(Error::other(format!(
+            "the documentation steps of {repository} are new or changed, run breath in a terminal to review them or set BREATH_TRUST=1 to trust them"
+        )));
+    }
+    println!("The documentation steps of {repository} are new or changed:");
+    for entry in documentation.doc.iter().chain(&documentation.man) {
+        println!("\t{entry}");
+    }
+    if !Confirm::new("Trust and run these steps?")
+        .with_default(false)
+        .prompt()
+        .map_err(Error::other)?
+    {
+        return Ok(false);
+    }
+    trusted.insert(repository, digest);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_yaml::to_string(&trusted).map_err(Error::other)?)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Entry, Task, default_allow, dependencies, normalize};
+    use std::path::{Path, PathBuf};
+
+    fn task(line: &str, outputs: &[&str]) -> Task {
+        Task {
+            outputs: outputs.iter().map(ToString::to_string).collect(),
+            ..Entry::Line(line.to_string()).task()
+        }
+    }
+
+    #[test]
+    fn normalizes_inside_the_repository() {
+        let base = Path::new("book");
+        assert_eq!(
+            normalize(base, "../docs/./book"),
+            Some(PathBuf::from("docs/book"))
+        );
+        assert_eq!(normalize(Path::new(""), "docs/.."), Some(PathBuf::new()));
+        assert_eq!(normalize(Path::new(""), ".."), None);
+        assert_eq!(normalize(base, "../../etc"), None);
+        assert_eq!(normalize(Path::new(""), "/etc"), None);
+    }
+
+    #[test]
+    fn keeps_the_writers_in_their_outputs() {
+        let allow = default_allow();
+        assert!(task("cargo doc --no-deps", &[]).check(&allow).is_ok());
+        assert!(task("curl https://example.com", &[]).check(&allow).is_err());
+        assert!(
+            task("cp -r target/doc docs", &["docs"])
+                .check(&allow)
+                .is_ok()
+        );
+        assert!(
+            task("touch docs/.nojekyll", &["docs"])
+                .check(&allow)
+                .is_ok()
+        );
+        assert!(task("cp -T a docs", &["docs"]).check(&allow).is_ok());
+        assert!(task("mkdir -pm 755 docs", &["docs"]).check(&allow).is_ok());
+        assert!(
+            task("touch -d 2020-01-01 docs/x", &["docs"])
+                .check(&allow)
+                .is_ok()
+        );
+        assert!(
+            task("cp -S .old -- README.md docs", &["docs"])
+                .check(&allow)
+                .is_ok()
+        );
+        assert!(task("mkdir -m755 x docs", &["docs"]).check(&allow).is_err());
+        assert!(task("cp -r target/doc docs", &[]).check(&allow).is_err());
+        assert!(task("rm -rf docs target", &["docs"]).check(&allow).is_err());
+        assert!(task("rm -rf docs/../..", &["docs"]).check(&allow).is_err());
+        assert!(task("touch /tmp/x", &["docs"]).check(&allow).is_err());
+        assert!(task("touch x", &["/tmp"]).check(&allow).is_err());
+        let mut outside = task("touch x", &["x"]);
+        outside.cwd = Some(String::from("../elsewhere"));
+        assert!(outside.check(&allow).is_err());
+        for line in [
+            "cp -t docs README.md",
+            "cp -rt docs target/doc",
+            "cp --target-directory=docs README.md",
+        ] {
+            let error = task(line, &["docs"]).check(&allow).expect_err(line);
+            assert!(error.to_string().contains("-t"));
+        }
+    }
+
+    #[test]
+    fn turns_a_writing_line_into_a_task() {
+        let task = Entry::Line(String::from("cp -r target/doc ./docs/"))
+            .with_outputs()
+            .expect("writer");
+        assert_eq!(task.outputs, ["docs"]);
+        assert_eq!(Entry::Line(String::from("cargo doc")).with_outputs(), None);
+        assert_eq!(Entry::Line(String::from("touch ../x")).with_outputs(), None);
+        assert_eq!(Entry::Task(task).with_outputs(), None);
+    }
+
+    #[test]
+    fn orders_the_steps() {
+        let step = |name: &str, needs: Option<&[&str]>| Task {
+            name: Some(name.to_string()),
+            needs: needs.map(|needs| needs.iter().map(ToString::to_string).collect()),
+            ..task("cargo", &[])
+        };
+        let tasks = [
+            step("api", None),
+            step("book", Some(&[])),
+            step("check", None),
+            step("publish", Some(&["api", "book"])),
+        ];
+        assert_eq!(
+            dependencies(&tasks).expect("dependencies"),
+            [vec![], vec![], vec![1], vec![0, 1]]
+        );
+        let error = dependencies(&[step("api", Some(&["book"]))]).expect_err("unknown");
+        assert!(error.to_string().contains("unknown step `book`"));
+        let cycle = [
+            step("api", Some(&["publish"])),
+            step("book", Some(&["api"])),
+            step("publish", Some(&["book"])),
+        ];
+        let error = dependencies(&cycle).expect_err("cycle");
+        assert!(error.to_string().contains("depends on itself"));
+        let error = dependencies(&[step("api", Some(&["api"]))]).expect_err("self");
+        assert!(error.to_string().contains("`api`"));
+        let error = dependencies(&[step("api", None), step("api", Some(&[]))]).expect_err("twice");
+        assert!(error.to_string().contains("`api` is used twice"));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::commit::{draft_path, vcs};
+use crate::config::{Config, Member};
+use crate::utils::EmailValidator;
+use inquire::validator::{StringValidator, Validation};
+use std::collections::BTreeMap;
+use std::io::Error;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[doc = "The author identity configured in the VCS"]
+pub struct Identity {
+    pub name: String,
+    pub email: String,
+}
+
+fn config_value(program: &str, key: &str) -> Option<String> {
+    let output = Command::new(program).arg("config").arg(key).output().ok()?;
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !value.is_empty()).then_some(value)
+}
+
+///
+/// Read the author identity from `git config` or `hg config`
+///
+#[must_use]
+pub fn identity() -> Option<Identity> {
+    if vcs().eq("hg") {
+        let username = config_value("hg", "ui.username")?;
+        let (name, email) = username.split_once('<').unwrap_or((username.as_str(), ""));
+        Some(Identity {
+            name: name.trim().to_string(),
+            email: email.trim_end_matches('>').trim().to_string(),
+        })
+    } else {
+        Some(Identity {
+            name: config_value("git", "user.name").unwrap_or_default(),
+            email: config_value("git", "user.email").unwrap_or_default(),
+        })
+        .filter(|identity| !identity.name.is_empty() || !identity.email.is_empty())
+    }
+}
+
+///
+/// Check if a text is a valid email
+///
+#[must_use]
+pub fn is_email(email: &str) -> bool {
+    matches!(EmailValidator.validate(email), Ok(Validation::Valid))
+}
+
+///
+/// Find a member of the team roster by handle
+///
+#[must_use]
+pub fn member<'a>(config: &'a Config, handle: &str) -> Option<&'a Member> {
+    config.team.iter().find(|member| member.handle.eq(handle))
+}
+
+///
+/// The handle of the current user, from the roster or the VCS identity
+///
+#[must_use]
+pub fn me(config: &Config) -> String {
+    let identity = identity().unwrap_or_default();
+    if let Some(member) = config.team.iter().find(|member| {
+        (!member.email.is_empty() && member.email.eq_ignore_ascii_case(&identity.email))
+            || (!member.name.is_empty() && member.name.eq(&identity.name))
+    }) {
+        return member.handle.clone();
+    }
+    if let Some((local, _)) = identity.email.split_once('@') {
+        return local.to_string();
+    }
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or(identity.name)
+}
+
+///
+/// The emails of the current user, from the VCS identity and the roster entry of its handle
+///
+#[must_use]
+pub fn my_emails(config: &Config, handle: &str) -> Vec<String> {
+    identity()
+        .map(|identity| identity.email)
+        .into_iter()
+        .chain(member(config, handle).map(|member| member.email.clone()))
+        .filter(|email| !email.is_empty())
+        .collect()
+}
+
+///
+/// Check if a member is the current user, by handle or by email
+///
+#[must_use]
+pub fn is_me(member: &Member, handle: &str, emails: &[String]) -> bool {
+    member.handle.eq(handle)
+        || emails
+            .iter()
+            .any(|email| member.email.eq_ignore_ascii_case(email))
+}
+
+///
+/// The authors of the recent history, as members without roles
+///
+#[must_use]
+pub fn history_authors() -> Vec<Member> {
+    let output = if vcs().eq("hg") {
+        Command::new("hg")
+            .args(["log", "-l", "500", "-T", "{author}\n"])
+            .output()
+    } else {
+        Command::new("git")
+            .args(["log", "-n", "500", "--format=%an <%ae>"])
+            .output()
+    };
+    let mut authors: Vec<Member> = Vec::new();
+    let Ok(output) = output else {
+        return authors;
+    };
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let (name, email) = line.split_once('<').unwrap_or((line, ""));
+        let email = email.trim_end_matches('>').trim();
+        if email.is_empty()
+            || authors
+                .iter()
+                .any(|author| author.email.eq_ignore_ascii_case(email))
+        {
+            continue;
+        }
+        authors.push(Member {
+            handle: email.split('@').next().unwrap_or(email).to_string(),
+            name: name.trim().to_string(),
+            email: email.to_string(),
+            roles: Vec::new(),
+        });
+    }
+    authors
+}
+
+fn roles_path() -> PathBuf {
+    draft_path().with_file_name("roles.yml")
+}
+
+///
+/// The roles last chosen by a user
+///
+#[must_use]
+pub fn remembered_roles(handle: &str) -> Vec<String> {
+    std::fs::read_to_string(roles_path())
+        .ok()
+        .and_then(|content| serde_yaml::from_str::<BTreeMap<String, Vec<String>>>(&content).ok())
+        .and_then(|mut roles| roles.remove(handle))
+        .unwrap_or_default()
+}
+
+///
+/// Remember the roles chosen by a user for the next commits
+///
+/// # Errors
+///
+/// Returns an error if the roles file cannot be written.
+pub fn remember_roles(handle: &str, roles: &[String]) -> Result<(), Error> {
+    let path = roles_path();
+    let mut all = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_yaml::from_str::<BTreeMap<String, Vec<String>>>(&content).ok())
+        .unwrap_or_default();
+    all.insert(handle.to_string(), roles.to_vec());
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_yaml::to_string(&all).map_err(Error::other)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_me;
+    use crate::config::Member;
+
+    fn member(handle: &str, email: &str) -> Member {
+        Member {
+            handle: handle.to_string(),
+            name: String::new(),
+            email: email.to_string(),
+            roles: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn recognizes_the_current_user() {
+        let emails = [String::from("Alice@Example.com")];
+        assert!(is_me(&member("alice", ""), "alice", &emails));
+        assert!(is_me(
+            &member("a.smith", "alice@example.com"),
+            "alice",
+            &emails
+        ));
+        assert!(!is_me(&member("bob", "bob@example.com"), "alice", &emails));
+        assert!(!is_me(&member("bob", ""), "alice", &[]));
+    }
+}
@@ -1,17 +1,14 @@
-use crate::commit::{Commit, run_commit, vcs};
 use crate::config::load_config;
-use breathes::hooks::run_hooks;
-use crossterm::cursor::MoveTo;
-use crossterm::execute;
 use crossterm::style::Stylize;
-use crossterm::terminal::{Clear, ClearType};
+use inquire::CustomUserError;
 use inquire::validator::{StringValidator, Validation};
-use inquire::{Confirm, CustomUserError, InquireError, Select};
 use regex::Regex;
 use spinners::{Spinner, Spinners};
-use std::fmt::{Display, Formatter};
-use std::io::{Error, stdout};
-use std::process::{Command, Stdio, exit};
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub const OK: i32 = 7;
 pub const KO: i32 = 8;
@@ -30,103 +27,237 @@ impl StringValidator for EmailValidator {
     }
 }
 
-/// Executes a command and provides real-time visual feedback while processing.
 ///
-/// This function displays a spinner animation while a command is executed. If the command
-/// succeeds, the spinner stops with a success message. If the command fails, the spinner
-/// stops with the error output displayed and an error is returned.
+/// The directory keeping the output of every command breath runs
 ///
-/// # Arguments
+pub const LOGS_PATH: &str = ".breath/logs";
+
+const TAIL: usize = 10;
+
 ///
-/// * `message` - A message to be displayed alongside the spinner while the command runs.
-/// * `cmd` - A mutable reference to a `Command` object that specifies the process to be executed.
-/// * `success` - A string message displayed when the command executes successfully.
-/// * `failure` - A string error message returned when the command fails.
-/// * `file` - A file name used to store the command's stdout and stderr logs under the `.breathes` directory.
+/// The number of command logs kept in `.breath/logs`, the oldest being removed first
 ///
-/// # Returns
+const LOGS_KEPT: usize = 50;
+
 ///
-/// Returns `Ok(())` if the command executes successfully, or `Err(std::io::Error)` if the command fails.
+/// Create the `.breath` directory, keeping its logs and its step cache out of the repository
 ///
-/// # Side Effects
+/// git reads the `.gitignore` written in the directory, mercurial the pattern added to the root `.hgignore`.
 ///
-/// - The function creates two files in the `.breathes` directory:
-///   - `stdout/{file}`: Stores the standard output of the command.
-///   - `stderr/{file}`: Stores the standard error of the command.
-/// - If these files cannot be created or the command cannot be executed, the function panics with an appropriate error message.
+/// # Errors
+/// If the directory or an ignore file cannot be written
+pub fn breath_dir() -> Result<(), Error> {
+    std::fs::create_dir_all(".breath")?;
+    let gitignore = Path::new(".breath/.gitignore");
+    if !gitignore.exists() {
+        std::fs::write(gitignore, "logs/\ncache.yml\n")?;
+    }
+    if Path::new(".hg").is_dir() {
+        let pattern = r"re:^\.breath/(logs/|cache\.yml$)";
+        let mut hgignore = std::fs::read_to_string(".hgignore").unwrap_or_default();
+        if !hgignore.lines().any(|line| line.trim().eq(pattern)) {
+            if !hgignore.is_empty() && !hgignore.ends_with('\n') {
+                hgignore.push('\n');
+            }
+            hgignore.push_str(pattern);
+            hgignore.push('\n');
+            std::fs::write(".hgignore", hgignore)?;
+        }
+    }
+    Ok(())
+}
+
+fn prune_logs() -> Result<(), Error> {
+    let mut logs = std::fs::read_dir(LOGS_PATH)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext.eq("log")))
+        .collect::<Vec<PathBuf>>();
+    logs.sort();
+    for log in &logs[..logs.len().saturating_sub(LOGS_KEPT)] {
+        std::fs::remove_file(log)?;
+    }
+    Ok(())
+}
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+///
+/// Stream the output of the commands breath runs instead of capturing it
+///
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+///
+/// Whether the output of the commands is streamed
+///
+#[must_use]
+pub fn verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}
+
+fn log_path(step: &str) -> PathBuf {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default();
+    let step = step
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .take(48)
+        .collect::<String>();
+    Path::new(LOGS_PATH).join(format!("{timestamp}-{}.log", step.trim_matches('-')))
+}
+
+///
+/// Run a command for a step, streaming its output when verbose, else keeping it in a log file
 ///
-/// # Panics
-/// if command is not founded and if the process cannot be spawned.
 /// # Errors
+/// If the command cannot be started or fails, with the tail of its stderr and the path of its log
+pub fn capture(cmd: &mut Command, step: &str) -> Result<(), Error> {
+    if verbose() {
+        let status = cmd
+            .stdin(Stdio::null())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .status()?;
+        return if status.success() {
+            Ok(())
+        } else {
+            Err(Error::other(format!("{step} exited with {status}")))
+        };
+    }
+    let output = cmd.stdin(Stdio::null()).output()?;
+    let path = log_path(step);
+    let log = breath_dir()
+        .and_then(|()| std::fs::create_dir_all(LOGS_PATH))
+        .and_then(|()| {
+            let mut content = output.stdout.clone();
+            content.extend_from_slice(&output.stderr);
+            std::fs::write(&path, content)
+        })
+        .and_then(|()| prune_logs());
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let lines = stderr.lines().collect::<Vec<&str>>();
+    let mut message = format!("{step} exited with {}", output.status);
+    for line in &lines[lines.len().saturating_sub(TAIL)..] {
+        message.push_str(format!("\n    {line}").as_str());
+    }
+    if log.is_ok() {
+        message.push_str(format!("\n  full output in {}", path.display()).as_str());
+    }
+    Err(Error::other(message))
+}
+
 ///
-/// - Returns `Err(std::io::Error)` with the failure message if the command exits unsuccessfully.
-/// - Returns `Err(std::io::Error)` if there is an issue reading the command's stderr output.
+/// Run a command behind a spinner, printing the tail of its stderr when it fails
 ///
-/// # Examples
+/// The output of the command is kept in `.breath/logs`, or streamed with `--verbose`.
 ///
-/// ```no_run
-/// use std::process::Command;
-/// use std::io;
+/// # Errors
+/// If the command cannot be started or fails
 ///
-/// fn main() -> io::Result<()> {
-///     let mut cmd = Command::new("ls");
-///     ok(
-///         "Executing command...",
-///         &mut cmd,
-///         "Command executed successfully!",
-///         "Command execution failed.",
-///         "output.log",
-///     )
-/// }
-/// ```
 pub fn ok(message: &str, cmd: &mut Command, success: &str, failure: &str) -> Result<(), Error> {
+    if verbose() {
+        println!("{}", message.white());
+        return match capture(cmd, message) {
+            Ok(()) => {
+                println!("{} {}", "✓".green(), success.dark_cyan());
+                Ok(())
+            }
+            Err(e) => {
+                eprintln!("{} {}", "!".red(), failure.yellow());
+                Err(e)
+            }
+        };
+    }
     let mut output = Spinner::new(Spinners::Line, message.white().to_string());
-    let status = cmd
-        .current_dir(".")
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .spawn()?
-        .wait()?
-        .code();
-    if let Some(response) = status
-        && response.eq(&0)
-    {
-        output.stop_and_persist(
-            "✓".green().to_string().as_str(),
-            success.dark_cyan().to_string(),
-        );
-        Ok(())
-    } else {
-        output.stop_and_persist("!".red().to_string().as_str(), failure.yellow().to_string());
-        Err(Error::other(failure))
+    match capture(cmd, message) {
+        Ok(()) => {
+            output.stop_and_persist(
+                "✓".green().to_string().as_str(),
+                success.dark_cyan().to_string(),
+            );
+            Ok(())
+        }
+        Err(e) => {
+            output.stop_and_persist("!".red().to_string().as_str(), failure.yellow().to_string());
+            eprintln!("{}", e.to_string().dark_grey());
+            Err(Error::other(failure))
+        }
     }
 }
 ///
-/// Call git or mercurial with arg
+/// Call git or mercurial with arguments, without a shell
 ///
 /// # Errors
 ///
-/// On failure to execute the command
+/// If the program cannot be started or fails
 ///
-pub fn call(program: &str, arg: &str) -> Result<i32, Error> {
-    let to = if arg.is_empty() {
-        program.to_string()
-    } else {
-        format!("{program} {arg}")
-    };
-    if !Command::new("sh")
-        .arg("-c")
-        .arg(to.as_str())
-        .current_dir(".")
-        .spawn()?
-        .wait()?
-        .success()
-    {
-        return Err(Error::other(format!("{program} not founded")));
+pub fn call(program: &str, args: &[&str]) -> Result<i32, Error> {
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .map_err(|e| Error::other(format!("failed to run {program}: {e}")))?;
+    if !status.success() {
+        return Err(Error::other(format!(
+            "{program} {} exited with {status}",
+            args.join(" ")
+        )));
     }
     Ok(OK)
 }
 
+fn limit<'a>(flag: &'a str, args: &mut impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    match flag {
+        "-n" | "--max-count" | "-l" | "--limit" => args.next(),
+        _ => flag
+            .strip_prefix("--max-count=")
+            .or_else(|| flag.strip_prefix("--limit="))
+            .or_else(|| flag.strip_prefix("-n"))
+            .or_else(|| flag.strip_prefix("-l"))
+            .or_else(|| flag.strip_prefix('-'))
+            .filter(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit())),
+    }
+}
+
+///
+/// Translate the flags of a proxied command whose meaning differs between git and mercurial
+///
+/// The log limit (`-n 5`, `-5`, `--max-count=5`, `-l 5`, `--limit 5`) and `--oneline` are written the way `vcs`
+/// expects, `status --short` is dropped on mercurial where it is the default, and the rest is kept as it is.
+///
+#[must_use]
+pub fn translate(vcs: &str, cmd: &str, args: &[String]) -> Vec<String> {
+    let hg = vcs.eq("hg");
+    let mut translated = vec![cmd.to_string()];
+    let mut args = args.iter().map(String::as_str);
+    while let Some(arg) = args.next() {
+        match (cmd, arg) {
+            ("log", "--") => {
+                translated.push(arg.to_string());
+                translated.extend(args.by_ref().map(String::from));
+            }
+            ("log", "--oneline") if hg => translated.extend([
+                "--template".to_string(),
+                "{node|short} {desc|firstline}\n".to_string(),
+            ]),
+            ("status", "-s" | "--short") if hg => {}
+            ("log", flag) if flag.starts_with('-') => match limit(flag, &mut args) {
+                Some(n) if hg => translated.extend(["--limit".to_string(), n.to_string()]),
+                Some(n) => translated.extend(["-n".to_string(), n.to_string()]),
+                None => translated.push(flag.to_string()),
+            },
+            _ => translated.push(arg.to_string()),
+        }
+    }
+    translated
+}
+
 /// # Panics
 /// if failed to parse breathes.toml
 #[must_use]
@@ -137,124 +268,55 @@ pub fn types() -> Vec<String> {
     types
 }
 
-impl Display for ZenOption {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Quit => write!(f, "Quit"),
-            Self::Add => write!(f, "Add"),
-            Self::Health => write!(f, "Health"),
-            Self::Log => write!(f, "Log"),
-            Self::Diff => write!(f, "Diff"),
-            Self::Email => write!(f, "Email"),
-            Self::ListTags => write!(f, "List Tags"),
-            Self::Status => write!(f, "Status"),
-            Self::Edit => write!(f, "Editor"),
-            Self::Commit => write!(f, "Commit"),
-        }
-    }
-}
+#[cfg(test)]
+mod tests {
+    use super::translate;
 
-#[derive(Ord, PartialOrd, Eq, PartialEq, Debug, Clone, Hash, Copy)]
-pub enum ZenOption {
-    Quit,
-    Add,
-    Edit,
-    Commit,
-    Health,
-    Log,
-    Diff,
-    Email,
-    Status,
-    ListTags,
-}
-impl ZenOption {
-    #[must_use]
-    pub fn all() -> Vec<Self> {
-        let mut x = vec![
-            Self::Quit,
-            Self::Add,
-            Self::Edit,
-            Self::Commit,
-            Self::Health,
-            Self::Log,
-            Self::Diff,
-            Self::Email,
-            Self::Status,
-            Self::ListTags,
-        ];
-        x.sort();
-        x
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
     }
-}
-impl From<&str> for ZenOption {
-    fn from(value: &str) -> Self {
-        match value {
-            "Add" => Self::Add,
-            "Health" => Self::Health,
-            "Log" => Self::Log,
-            "Diff" => Self::Diff,
-            "Email" => Self::Email,
-            "ListTags" => Self::ListTags,
-            "Status" => Self::Status,
-            "Editor" => Self::Edit,
-            "Commit" => Self::Commit,
-            _ => Self::Quit,
+
+    #[test]
+    fn translates_the_log_flags() {
+        for line in [
+            "-n 5",
+            "-5",
+            "--max-count=5",
+            "-l 5",
+            "--limit 5",
+            "--limit=5",
+            "-n5",
+        ] {
+            assert_eq!(
+                translate("git", "log", &args(line)),
+                args("log -n 5"),
+                "{line}"
+            );
+            assert_eq!(
+                translate("hg", "log", &args(line)),
+                args("log --limit 5"),
+                "{line}"
+            );
         }
+        assert_eq!(
+            translate("hg", "log", &args("--oneline -p")),
+            ["log", "--template", "{node|short} {desc|firstline}\n", "-p"]
+        );
+        assert_eq!(
+            translate("git", "log", &args("--oneline")),
+            args("log --oneline")
+        );
+        assert_eq!(
+            translate("hg", "log", &args("-3 -- -5 README.md")),
+            args("log --limit 3 -- -5 README.md")
+        );
     }
-}
 
-///
-/// Interact with the developer.
-///
-/// # Panics
-/// On bad input
-/// # Errors
-/// - If there is an issue reading the command's stderr output.
-/// - If there is an issue executing a command.
-/// - If there is an issue creating a file.
-/// - If there is an issue clearing the terminal screen.
-/// - If there is an issue writing to a file.
-/// # Dependencies
-/// - `crossterm` for terminal manipulation.
-/// - `git` for version control.
-/// - `git-commit-template` for commit templates.
-///
-pub fn zen() -> Result<i32, InquireError> {
-    loop {
-        execute!(stdout(), Clear(ClearType::All), MoveTo(0, 0))?;
-        let option = Select::new(
-            "@wishes:".green().bold().to_string().as_str(),
-            ZenOption::all(),
-        )
-        .prompt()?;
-        let response: Result<i32, Error> = match option {
-            ZenOption::Quit => {
-                execute!(stdout(), Clear(ClearType::All), MoveTo(0, 0))?;
-                exit(QUIT);
-            }
-            ZenOption::Add => call(vcs().as_str(), "add ."),
-            ZenOption::Health => run_hooks(),
-            ZenOption::Log => call(vcs().as_str(), "log"),
-            ZenOption::Status => call(vcs().as_str(), "status"),
-            ZenOption::Diff => call(vcs().as_str(), "diff"),
-            ZenOption::Email => call("aerc", ""),
-            ZenOption::ListTags => call(vcs().as_str(), "tag"),
-            ZenOption::Edit => call("broot", "."),
-            ZenOption::Commit => {
-                if run_hooks().is_ok()
-                    && let Ok(c) = Commit::default().commit()
-                    && run_commit(c).is_ok()
-                {
-                    call(vcs().as_str(), "push")
-                } else {
-                    continue;
-                }
-            }
-        };
-        if response.is_err() && Confirm::new("Exit").with_default(false).prompt()?
-            || response.is_ok() && Confirm::new("Exit").with_default(false).prompt()?
-        {
-            exit(QUIT);
-        }
+    #[test]
+    fn translates_only_the_known_flags() {
+        assert_eq!(translate("hg", "status", &args("-s")), args("status"));
+        assert_eq!(translate("hg", "status", &args("--short")), args("status"));
+        assert_eq!(translate("git", "status", &args("-s")), args("status -s"));
+        assert_eq!(translate("git", "diff", &args("-n 5")), args("diff -n 5"));
     }
 }
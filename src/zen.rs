@@ -0,0 +1,798 @@
+use crate::commit::{branch, staged_files, vcs};
+use crate::config::try_load_config;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::execute;
+use crossterm::terminal::{Clear, ClearType};
+use ratatui::DefaultTerminal;
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::io::{Error, Write, stdin, stdout};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::LazyLock;
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::time::Duration;
+
+const LOG_SIZE: &str = "30";
+
+#[derive(Ord, PartialOrd, Eq, PartialEq, Debug, Clone, Hash, Copy)]
+pub enum ZenOption {
+    Diff,
+    Health,
+    ListTags,
+    Refresh,
+    Quit,
+}
+
+impl ZenOption {
+    #[must_use]
+    pub fn all() -> Vec<Self> {
+        vec![
+            Self::Diff,
+            Self::Health,
+            Self::ListTags,
+            Self::Refresh,
+            Self::Quit,
+        ]
+    }
+
+    ///
+    /// The key running the action
+    ///
+    #[must_use]
+    pub const fn key(self) -> char {
+        match self {
+            Self::Diff => 'd',
+            Self::Health => 'h',
+            Self::ListTags => 't',
+            Self::Refresh => 'r',
+            Self::Quit => 'q',
+        }
+    }
+}
+
+impl Display for ZenOption {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Diff => write!(f, "whole diff"),
+            Self::Health => write!(f, "health"),
+            Self::ListTags => write!(f, "tags"),
+            Self::Refresh => write!(f, "refresh"),
+            Self::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+///
+/// The keys moving in the view, which no action can take
+///
+const MOVES: [char; 4] = ['j', 'k', 'J', 'K'];
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+#[doc = "An action of the zen menu"]
+pub struct ZenAction {
+    /// The label shown next to the key
+    pub label: String,
+    /// The key running the action
+    pub key: char,
+    /// The program and its arguments, `{vcs}` is replaced by git or hg, `{file}` by the selected file and `breath`
+    /// runs breath itself
+    pub command: Vec<String>,
+    /// Ask before running the action
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub confirm: bool,
+    /// Hand the terminal to the program instead of running it in the background
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub interactive: bool,
+}
+
+impl ZenAction {
+    fn new(label: &str, key: char, command: &[&str]) -> Self {
+        Self {
+            label: label.to_string(),
+            key,
+            command: command.iter().map(ToString::to_string).collect(),
+            confirm: false,
+            interactive: false,
+        }
+    }
+
+    const fn confirm(mut self) -> Self {
+        self.confirm = true;
+        self
+    }
+
+    const fn interactive(mut self) -> Self {
+        self.interactive = true;
+        self
+    }
+
+    fn program(&self, vcs: &str) -> Option<String> {
+        let program = self.command.first()?.replace("{vcs}", vcs);
+        if program.eq("breath") {
+            std::env::current_exe()
+                .ok()
+                .map(|exe| exe.to_string_lossy().to_string())
+        } else {
+            Some(program)
+        }
+    }
+
+    fn args(&self, vcs: &str, file: Option<&str>) -> Option<Vec<String>> {
+        self.command
+            .iter()
+            .skip(1)
+            .map(|arg| {
+                let arg = arg.replace("{vcs}", vcs);
+                if arg.contains("{file}") {
+                    file.map(|file| arg.replace("{file}", file))
+                } else {
+                    Some(arg)
+                }
+            })
+            .collect()
+    }
+}
+
+///
+/// The actions of the zen menu when breath.yml defines none
+///
+#[must_use]
+pub fn default_zen() -> Vec<ZenAction> {
+    vec![
+        ZenAction::new("add", 'a', &["{vcs}", "add", "{file}"]),
+        ZenAction::new("add all", 'A', &["{vcs}", "add", "."]),
+        ZenAction::new("commit", 'c', &["breath", "commit"]).interactive(),
+        ZenAction::new("push", 'p', &["{vcs}", "push"]).confirm(),
+        ZenAction::new("editor", 'e', &["broot", "."]).interactive(),
+        ZenAction::new("email", 'm', &["aerc"]).interactive(),
+    ]
+}
+
+fn installed(program: &str) -> bool {
+    let path = Path::new(program);
+    if path.components().count() > 1 {
+        return path.is_file();
+    }
+    std::env::var_os("PATH").is_some_and(|paths| {
+        std::env::split_paths(&paths).any(|dir| {
+            dir.join(program).is_file()
+                || cfg!(windows) && dir.join(format!("{program}.exe")).is_file()
+        })
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Job {
+    Health,
+    Action(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Health {
+    Unknown,
+    Running,
+    Passed,
+    Failed,
+}
+
+struct Zen {
+    vcs: String,
+    branch: String,
+    status: Vec<String>,
+    files: ListState,
+    staged: Vec<String>,
+    log: Vec<String>,
+    tags: bool,
+    health: Health,
+    checks: Vec<String>,
+    diff: Vec<String>,
+    whole: bool,
+    scroll: u16,
+    message: (String, Color),
+    actions: Vec<ZenAction>,
+    pending: Option<usize>,
+    sender: Sender<(Job, bool, Vec<String>)>,
+    receiver: Receiver<(Job, bool, Vec<String>)>,
+}
+
+///
+/// The escape sequences coloring or moving the output of a program
+///
+static ANSI: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\x1b\[[0-9;?]*[A-Za-z]").expect("bad regex"));
+
+fn lines(program: &str, args: &[&str]) -> (bool, Vec<String>) {
+    match Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+    {
+        Ok(output) => {
+            let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+            text.push_str(String::from_utf8_lossy(&output.stderr).as_ref());
+            let lines = text
+                .lines()
+                .map(|line| {
+                    let line = line.rsplit('\r').next().unwrap_or_default();
+                    ANSI.replace_all(line, "").trim_end().to_string()
+                })
+                .filter(|line| !line.is_empty())
+                .collect();
+            (output.status.success(), lines)
+        }
+        Err(e) => (false, vec![format!("failed to run {program}: {e}")]),
+    }
+}
+
+///
+/// Keep the actions whose key is free and whose program is installed, returning the reasons the others are hidden
+///
+fn visible(
+    configured: Vec<ZenAction>,
+    vcs: &str,
+    installed: impl Fn(&str) -> bool,
+) -> (Vec<ZenAction>, Vec<String>) {
+    let reserved = ZenOption::all()
+        .iter()
+        .map(|option| option.key())
+        .chain(MOVES)
+        .collect::<Vec<char>>();
+    let mut actions: Vec<ZenAction> = Vec::new();
+    let mut hidden = Vec::new();
+    for action in configured {
+        let taken = reserved.contains(&action.key) || actions.iter().any(|a| a.key == action.key);
+        if taken {
+            hidden.push(format!("{} (key {} taken)", action.label, action.key));
+        } else if action.program(vcs).is_some_and(|p| installed(p.as_str())) {
+            actions.push(action);
+        } else {
+            hidden.push(format!("{} (not installed)", action.label));
+        }
+    }
+    (actions, hidden)
+}
+
+impl Zen {
+    fn new() -> Self {
+        let (sender, receiver) = channel();
+        let vcs = vcs();
+        let (actions, hidden) = visible(
+            try_load_config().map_or_else(|_| default_zen(), |config| config.zen),
+            vcs.as_str(),
+            installed,
+        );
+        let message = if hidden.is_empty() {
+            (String::new(), Color::Reset)
+        } else {
+            (format!("hidden: {}", hidden.join(", ")), Color::Yellow)
+        };
+        let mut zen = Self {
+            vcs,
+            branch: String::new(),
+            status: Vec::new(),
+            files: ListState::default().with_selected(Some(0)),
+            staged: Vec::new(),
+            log: Vec::new(),
+            tags: false,
+            health: Health::Unknown,
+            checks: Vec::new(),
+            diff: Vec::new(),
+            whole: false,
+            scroll: 0,
+            message,
+            actions,
+            pending: None,
+            sender,
+            receiver,
+        };
+        zen.refresh();
+        zen
+    }
+
+    fn hg(&self) -> bool {
+        self.vcs.eq("hg")
+    }
+
+    fn refresh(&mut self) {
+        let selected = self.selected();
+        self.branch = branch().unwrap_or_default();
+        self.status = if self.hg() {
+            lines("hg", &["status"]).1
+        } else {
+            lines("git", &["status", "--porcelain"]).1
+        };
+        let last = self.status.len().saturating_sub(1);
+        let index = self
+            .status
+            .iter()
+            .position(|line| selected.is_some() && self.path(line) == selected)
+            .unwrap_or_else(|| self.files.selected().unwrap_or_default().min(last));
+        self.files.select(Some(index));
+        self.staged = staged_files();
+        self.log = match (self.hg(), self.tags) {
+            (true, true) => lines("hg", &["tags"]).1,
+            (true, false) => {
+                lines(
+                    "hg",
+                    &[
+                        "log",
+                        "-l",
+                        LOG_SIZE,
+                        "-T",
+                        "{node|short} {desc|firstline}\n",
+                    ],
+                )
+                .1
+            }
+            (false, true) => lines("git", &["tag", "--sort=-creatordate"]).1,
+            (false, false) => lines("git", &["log", "--oneline", "-n", LOG_SIZE]).1,
+        };
+        self.load_diff();
+    }
+
+    fn path(&self, line: &str) -> Option<String> {
+        let path = line.get(if self.hg() { 2 } else { 3 }..)?;
+        Some(
+            path.rsplit(" -> ")
+                .next()
+                .unwrap_or(path)
+                .trim_matches('"')
+                .to_string(),
+        )
+    }
+
+    fn selected(&self) -> Option<String> {
+        self.path(self.status.get(self.files.selected()?)?)
+    }
+
+    fn load_diff(&mut self) {
+        let file = if self.whole { None } else { self.selected() };
+        let mut args = vec!["diff"];
+        if !self.hg() {
+            args.push("HEAD");
+        }
+        args.push("--");
+        if let Some(file) = file.as_deref() {
+            args.push(file);
+        }
+        let (ok, mut diff) = lines(self.vcs.as_str(), &args);
+        if !ok && !self.hg() {
+            args.remove(1);
+            diff = lines(self.vcs.as_str(), &args).1;
+        }
+        if diff.is_empty() {
+            diff.push(String::from("no changes to show"));
+        }
+        self.diff = diff;
+        self.scroll = 0;
+    }
+
+    fn say(&mut self, message: impl Into<String>, success: bool) {
+        self.message = (
+            message.into(),
+            if success { Color::Green } else { Color::Red },
+        );
+    }
+
+    fn spawn(&mut self, job: Job, program: String, args: Vec<String>) {
+        let sender = self.sender.clone();
+        std::thread::spawn(move || {
+            let (ok, output) = lines(
+                program.as_str(),
+                &args.iter().map(String::as_str).collect::<Vec<&str>>(),
+            );
+            let _ = sender.send((job, ok, output));
+        });
+        match job {
+            Job::Health => {
+                self.health = Health::Running;
+                self.checks.clear();
+            }
+            Job::Action(index) => {
+                let message = format!("{}…", self.actions[index].label);
+                self.say(message, true);
+            }
+        }
+    }
+
+    fn check_health(&mut self) {
+        match std::env::current_exe() {
+            Ok(exe) => self.spawn(
+                Job::Health,
+                exe.to_string_lossy().to_string(),
+                vec![String::from("health")],
+            ),
+            Err(e) => self.say(e.to_string(), false),
+        }
+    }
+
+    fn finish(&mut self, job: Job, ok: bool, output: Vec<String>) {
+        match job {
+            Job::Health => {
+                self.health = if ok { Health::Passed } else { Health::Failed };
+                self.checks = output;
+            }
+            Job::Action(index) => {
+                let label = self.actions[index].label.clone();
+                let line = if ok { output.last() } else { output.first() };
+                let line = line.cloned().unwrap_or_default();
+                self.say(
+                    if ok {
+                        format!("{label} done {line}")
+                    } else {
+                        format!("{label} failed: {line}")
+                    },
+                    ok,
+                );
+                self.refresh();
+            }
+        }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal, index: usize) -> Result<(), Error> {
+        let action = self.actions[index].clone();
+        let file = self.selected();
+        let (Some(program), Some(args)) = (
+            action.program(self.vcs.as_str()),
+            action.args(self.vcs.as_str(), file.as_deref()),
+        ) else {
+            self.say(format!("{} needs a selected file", action.label), false);
+            return Ok(());
+        };
+        if !action.interactive {
+            self.spawn(Job::Action(index), program, args);
+            return Ok(());
+        }
+        let result = suspend(terminal, || {
+            let result = interact(
+                program.as_str(),
+                &args.iter().map(String::as_str).collect::<Vec<&str>>(),
+            );
+            if result.is_err() {
+                pause();
+            }
+            result
+        })?;
+        match result {
+            Ok(()) => self.say(format!("{} done", action.label), true),
+            Err(e) => self.say(e.to_string(), false),
+        }
+        self.refresh();
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, footer] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(frame.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(main);
+        let [status, staged, log, health] = Layout::vertical([
+            Constraint::Percentage(30),
+            Constraint::Percentage(20),
+            Constraint::Percentage(30),
+            Constraint::Percentage(20),
+        ])
+        .areas(left);
+        self.draw_status(frame, status);
+        frame.render_widget(
+            List::new(self.staged.iter().map(|file| file.as_str().green()))
+                .block(Block::bordered().title(format!(" Staged ({}) ", self.staged.len()))),
+            staged,
+        );
+        frame.render_widget(
+            Paragraph::new(
+                self.log
+                    .iter()
+                    .map(|line| match line.split_once(' ') {
+                        Some((id, rest)) if !self.tags => {
+                            Line::from(vec![id.dark_gray(), Span::raw(" "), Span::raw(rest)])
+                        }
+                        _ => Line::from(line.as_str()),
+                    })
+                    .collect::<Vec<Line>>(),
+            )
+            .block(Block::bordered().title(if self.tags { " Tags " } else { " Log " })),
+            log,
+        );
+        self.draw_health(frame, health);
+        self.draw_diff(frame, right);
+        self.draw_footer(frame, footer);
+    }
+
+    fn draw_status(&mut self, frame: &mut Frame, area: Rect) {
+        let hg = self.hg();
+        let items = self
+            .status
+            .iter()
+            .map(|line| {
+                let code = line.get(..if hg { 1 } else { 2 }).unwrap_or_default();
+                let color = if code.contains('?') {
+                    Color::Red
+                } else if !hg && !code.starts_with(' ') {
+                    Color::Green
+                } else {
+                    Color::Yellow
+                };
+                ListItem::new(line.as_str()).style(Style::new().fg(color))
+            })
+            .collect::<Vec<ListItem>>();
+        let title = if self.branch.is_empty() {
+            String::from(" Status ")
+        } else {
+            format!(" Status on {} ", self.branch)
+        };
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::bordered().title(title))
+                .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+                .highlight_symbol("> "),
+            area,
+            &mut self.files,
+        );
+    }
+
+    fn draw_health(&self, frame: &mut Frame, area: Rect) {
+        let (title, color) = match self.health {
+            Health::Unknown => (" Health · press h ", Color::Reset),
+            Health::Running => (" Health · running ", Color::Yellow),
+            Health::Passed => (" Health · passed ", Color::Green),
+            Health::Failed => (" Health · failed ", Color::Red),
+        };
+        let skip = self
+            .checks
+            .len()
+            .saturating_sub(usize::from(area.height.saturating_sub(2)));
+        frame.render_widget(
+            Paragraph::new(
+                self.checks[skip..]
+                    .iter()
+                    .map(|line| Line::from(line.as_str()))
+                    .collect::<Vec<Line>>(),
+            )
+            .block(
+                Block::bordered()
+                    .title(title)
+                    .border_style(Style::new().fg(color)),
+            ),
+            area,
+        );
+    }
+
+    fn draw_diff(&self, frame: &mut Frame, area: Rect) {
+        let title = if self.whole {
+            String::from(" Diff ")
+        } else {
+            format!(" Diff {} ", self.selected().unwrap_or_default())
+        };
+        let diff = self
+            .diff
+            .iter()
+            .map(|line| {
+                let style = if line.starts_with("+++") || line.starts_with("---") {
+                    Style::new().add_modifier(Modifier::BOLD)
+                } else if line.starts_with('+') {
+                    Style::new().fg(Color::Green)
+                } else if line.starts_with('-') {
+                    Style::new().fg(Color::Red)
+                } else if line.starts_with("@@") {
+                    Style::new().fg(Color::Cyan)
+                } else if line.starts_with("diff") || line.starts_with("index") {
+                    Style::new().fg(Color::DarkGray)
+                } else {
+                    Style::new()
+                };
+                Line::styled(line.as_str(), style)
+            })
+            .collect::<Vec<Line>>();
+        frame.render_widget(
+            Paragraph::new(diff)
+                .block(Block::bordered().title(title))
+                .scroll((self.scroll, 0)),
+            area,
+        );
+    }
+
+    fn draw_footer(&self, frame: &mut Frame, area: Rect) {
+        let mut keys = vec![
+            " ↑↓".bold(),
+            Span::raw(" select "),
+            " PgUp PgDn".bold(),
+            Span::raw(" scroll "),
+        ];
+        for action in &self.actions {
+            keys.push(format!(" {}", action.key).bold());
+            keys.push(Span::raw(format!(" {} ", action.label)));
+        }
+        for option in ZenOption::all() {
+            keys.push(format!(" {}", option.key()).bold());
+            keys.push(Span::raw(format!(" {option} ")));
+        }
+        let [shortcuts, message] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(area);
+        frame.render_widget(
+            Paragraph::new(Line::from(keys)).wrap(Wrap { trim: false }),
+            shortcuts,
+        );
+        frame.render_widget(
+            Paragraph::new(format!(" {}", self.message.0)).style(Style::new().fg(self.message.1)),
+            message,
+        );
+    }
+}
+
+fn suspend<T>(terminal: &mut DefaultTerminal, run: impl FnOnce() -> T) -> Result<T, Error> {
+    ratatui::try_restore()?;
+    let result = run();
+    *terminal = ratatui::try_init()?;
+    execute!(stdout(), Clear(ClearType::All))?;
+    Ok(result)
+}
+
+fn pause() {
+    print!("\npress enter to return to zen");
+    let _ = stdout().flush();
+    let _ = stdin().read_line(&mut String::new());
+}
+
+fn interact(program: &str, args: &[&str]) -> Result<(), Error> {
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .map_err(|e| Error::other(format!("failed to run {program}: {e}")))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::other(format!("{program} exited with {status}")))
+    }
+}
+
+fn run(terminal: &mut DefaultTerminal) -> Result<(), Error> {
+    let mut zen = Zen::new();
+    loop {
+        while let Ok((job, ok, output)) = zen.receiver.try_recv() {
+            zen.finish(job, ok, output);
+        }
+        terminal.draw(|frame| zen.draw(frame))?;
+        if !event::poll(Duration::from_millis(200))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        if let Some(index) = zen.pending.take() {
+            if key.code == KeyCode::Char('y') {
+                zen.run(terminal, index)?;
+            } else {
+                zen.say(format!("{} cancelled", zen.actions[index].label), false);
+            }
+            continue;
+        }
+        let option = match key.code {
+            KeyCode::Esc => Some(ZenOption::Quit),
+            KeyCode::Char(c) => ZenOption::all().into_iter().find(|o| o.key() == c),
+            _ => None,
+        };
+        let action = match key.code {
+            KeyCode::Char(c) => zen.actions.iter().position(|action| action.key == c),
+            _ => None,
+        };
+        if let Some(index) = action {
+            if zen.actions[index].confirm {
+                zen.pending = Some(index);
+                let message = format!("run {}? (y/n)", zen.actions[index].label);
+                zen.message = (message, Color::Yellow);
+            } else {
+                zen.run(terminal, index)?;
+            }
+            continue;
+        }
+        match (key.code, option) {
+            (_, Some(ZenOption::Quit)) => return Ok(()),
+            (_, Some(ZenOption::Diff)) => {
+                zen.whole = !zen.whole;
+                zen.load_diff();
+            }
+            (_, Some(ZenOption::Health)) if zen.health != Health::Running => {
+                zen.check_health();
+            }
+            (_, Some(ZenOption::ListTags)) => {
+                zen.tags = !zen.tags;
+                zen.refresh();
+            }
+            (_, Some(ZenOption::Refresh)) => zen.refresh(),
+            (KeyCode::Down | KeyCode::Char('j'), None) => {
+                if zen
+                    .files
+                    .selected()
+                    .is_some_and(|i| i + 1 < zen.status.len())
+                {
+                    zen.files.select_next();
+                }
+                zen.load_diff();
+            }
+            (KeyCode::Up | KeyCode::Char('k'), None) => {
+                zen.files.select_previous();
+                zen.load_diff();
+            }
+            (KeyCode::PageDown | KeyCode::Char('J'), None) => {
+                zen.scroll = zen.scroll.saturating_add(10);
+            }
+            (KeyCode::PageUp | KeyCode::Char('K'), None) => {
+                zen.scroll = zen.scroll.saturating_sub(10);
+            }
+            _ => {}
+        }
+    }
+}
+
+///
+/// Show the repository in a full-screen view, acting on it with single keys
+///
+/// # Errors
+/// If the terminal cannot be set up or drawn
+///
+pub fn zen() -> Result<(), Error> {
+    let mut terminal = ratatui::try_init()?;
+    let result = run(&mut terminal);
+    ratatui::try_restore()?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ZenAction, default_zen, visible};
+
+    #[test]
+    fn hides_the_taken_keys_and_the_missing_programs() {
+        let configured = vec![
+            ZenAction::new("quit twice", 'q', &["true"]),
+            ZenAction::new("down", 'j', &["true"]),
+            ZenAction::new("log", 'l', &["{vcs}", "log"]),
+            ZenAction::new("lazy", 'l', &["lazygit"]),
+            ZenAction::new("mail", 'm', &["aerc"]),
+        ];
+        let (actions, hidden) = visible(configured, "hg", |program| program.ne("aerc"));
+        assert_eq!(
+            actions
+                .iter()
+                .map(|a| a.label.as_str())
+                .collect::<Vec<&str>>(),
+            ["log"]
+        );
+        assert_eq!(
+            hidden,
+            [
+                "quit twice (key q taken)",
+                "down (key j taken)",
+                "lazy (key l taken)",
+                "mail (not installed)"
+            ]
+        );
+    }
+
+    #[test]
+    fn fills_the_vcs_and_the_file_in() {
+        let add = &default_zen()[0];
+        assert_eq!(add.program("hg").as_deref(), Some("hg"));
+        assert_eq!(
+            add.args("git", Some("src/main.rs")),
+            Some(vec![String::from("add"), String::from("src/main.rs")])
+        );
+        assert_eq!(add.args("git", None), None);
+        let push = &default_zen()[3];
+        assert_eq!(push.args("hg", None), Some(vec![String::from("push")]));
+    }
+}